@@ -0,0 +1,126 @@
+//! The derive macros for `api_error`, see the re-exports in that crate for their documentation.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitInt, LitStr, Variant};
+
+#[proc_macro_derive(Category, attributes(category))]
+pub fn derive_category(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_category(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_category(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            "`Category` can only be derived for enums",
+        ));
+    };
+
+    let mut status_arms = Vec::with_capacity(data.variants.len());
+    let mut code_arms = Vec::with_capacity(data.variants.len());
+    let mut errors: Option<Error> = None;
+    for variant in &data.variants {
+        match parse_variant(variant) {
+            Ok((status, code)) => {
+                let ident = &variant.ident;
+                let pattern = match &variant.fields {
+                    Fields::Named(_) => quote!(Self::#ident { .. }),
+                    Fields::Unnamed(_) => quote!(Self::#ident(..)),
+                    Fields::Unit => quote!(Self::#ident),
+                };
+                status_arms.push(quote!(#pattern => #status));
+                code_arms.push(quote!(#pattern => #code));
+            }
+            Err(e) => match &mut errors {
+                Some(errors) => errors.combine(e),
+                None => errors = Some(e),
+            },
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::api_error::Category for #name #ty_generics #where_clause {
+            fn status_code(&self) -> u16 {
+                match self {
+                    #(#status_arms,)*
+                }
+            }
+
+            fn code(&self) -> ::api_error::ErrorCode {
+                let code = match self {
+                    #(#code_arms,)*
+                };
+                ::api_error::ErrorCode::new(code).expect("the code is checked by the derive")
+            }
+        }
+    })
+}
+
+/// The status and code from the variant's `#[category(status = ..., code = ...)]`, both of which
+/// are required
+fn parse_variant(variant: &Variant) -> syn::Result<(LitInt, LitStr)> {
+    let missing = || {
+        Error::new_spanned(
+            variant,
+            format!(
+                "missing `#[category(status = ..., code = ...)]` on `{}`",
+                variant.ident
+            ),
+        )
+    };
+    let attr = variant
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("category"))
+        .ok_or_else(missing)?;
+
+    let mut status: Option<LitInt> = None;
+    let mut code: Option<LitStr> = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("status") {
+            let lit: LitInt = meta.value()?.parse()?;
+            match lit.base10_parse::<u16>() {
+                Ok(100..=599) => status = Some(lit),
+                _ => return Err(Error::new_spanned(lit, "the status must be within 100-599")),
+            }
+        } else if meta.path.is_ident("code") {
+            let lit: LitStr = meta.value()?.parse()?;
+            let value = lit.value();
+            let mut chars = value.chars();
+            let valid = chars.next().is_some_and(|c| c.is_ascii_uppercase())
+                && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                return Err(Error::new_spanned(
+                    lit,
+                    "the code should match `^[A-Z][A-Z0-9_]*$`",
+                ));
+            }
+            code = Some(lit);
+        } else {
+            return Err(meta.error("expected `status` or `code`"));
+        }
+        Ok(())
+    })?;
+
+    match (status, code) {
+        (Some(status), Some(code)) => Ok((status, code)),
+        (None, _) => Err(Error::new_spanned(
+            attr,
+            format!("missing `status = ...` for `{}`", variant.ident),
+        )),
+        (_, None) => Err(Error::new_spanned(
+            attr,
+            format!("missing `code = ...` for `{}`", variant.ident),
+        )),
+    }
+}
@@ -0,0 +1,110 @@
+//! Counts the heap allocations made when creating errors, run with
+//! `cargo bench --bench allocations`
+
+use api_error::{DetailedError, ToResponse};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[derive(Debug)]
+struct PublicError;
+
+impl ToResponse for PublicError {
+    type Response = &'static str;
+
+    fn to_response(&self) -> Self::Response {
+        "unavailable"
+    }
+}
+
+const ITERATIONS: usize = 1000;
+
+/// The average number of allocations made by each call of `f`
+fn allocations<T>(mut f: impl FnMut() -> T) -> f64 {
+    // The first call initialises the global configuration
+    drop(f());
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        drop(f());
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / ITERATIONS as f64
+}
+
+fn main() {
+    let no_fields = allocations(|| {
+        let io = std::io::Error::other("connection reset");
+        let err: DetailedError<PublicError, &str> =
+            api_error::w!(io, PublicError, "Upstream", "calling the upstream");
+        err
+    });
+    // What the file and module used to cost, when they were stored as `String`s
+    let owned_location = allocations(|| {
+        let io = std::io::Error::other("connection reset");
+        let err: DetailedError<PublicError, &str> =
+            api_error::w!(io, PublicError, "Upstream", "calling the upstream");
+        (err, file!().to_string(), module_path!().to_string())
+    });
+    let literal_keys = allocations(|| {
+        let io = std::io::Error::other("connection reset");
+        let err: DetailedError<PublicError, &str> = api_error::w!(
+            io,
+            PublicError,
+            "Upstream",
+            "calling the upstream",
+            "a" => 1,
+            "b" => 2,
+            "c" => 3,
+            "d" => 4,
+            "e" => 5,
+        );
+        err
+    });
+    let keys = ["a", "b", "c", "d", "e"].map(String::from);
+    let dynamic_keys = allocations(|| {
+        let io = std::io::Error::other("connection reset");
+        let err: DetailedError<PublicError, &str> = api_error::w!(
+            io,
+            PublicError,
+            "Upstream",
+            "calling the upstream",
+            keys[0].as_str() => 1,
+            keys[1].as_str() => 2,
+            keys[2].as_str() => 3,
+            keys[3].as_str() => 4,
+            keys[4].as_str() => 5,
+        );
+        err
+    });
+
+    println!("no fields: {:.1} allocations per error", no_fields);
+    println!(
+        "no fields, with an owned file and module: {:.1} allocations per error",
+        owned_location
+    );
+    println!(
+        "five literal keys: {:.1} allocations per error",
+        literal_keys
+    );
+    println!(
+        "five dynamic keys: {:.1} allocations per error",
+        dynamic_keys
+    );
+}
@@ -0,0 +1,68 @@
+//! A handler returning a [`DetailedError`] straight from an axum router
+//!
+//! `cargo run --example axum --features axum`
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use thiserror::Error as ThisError;
+use tower::ServiceExt;
+
+use std::fmt;
+
+use api_error::{e, DetailedError, ToResponse};
+
+#[derive(Debug, ThisError)]
+enum PublicError {
+    #[error("An unexpected server error occurred, please try again in 5 seconds.")]
+    UnexpectedServerError,
+}
+
+impl ToResponse for PublicError {
+    type Response = Value;
+
+    fn to_response(&self) -> Self::Response {
+        json!({
+            "category": format!("{:?}", self),
+            "msg": self.to_string(),
+        })
+    }
+}
+
+#[derive(Debug)]
+enum Category {
+    Database,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+async fn handler() -> Result<&'static str, DetailedError<PublicError, Category>> {
+    let err = std::io::Error::other("connection reset");
+    Err(e!(
+        err,
+        PublicError::UnexpectedServerError,
+        Category::Database,
+        "loading the user"
+    ))
+}
+
+#[tokio::main]
+async fn main() {
+    let app = Router::new().route("/", get(handler));
+
+    let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, PublicError::UnexpectedServerError.to_response());
+    println!("{}", body);
+}
@@ -0,0 +1,77 @@
+//! A filter rejecting with a [`DetailedError`], which is turned into the response by
+//! [`recover_rejection`]
+//!
+//! `cargo run --example warp --features warp`
+
+use serde_json::{json, Value};
+use thiserror::Error as ThisError;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use std::fmt;
+
+use api_error::{e, recover_rejection, DetailedError, ToResponse};
+
+#[derive(Debug, ThisError)]
+enum PublicError {
+    #[error("The user couldn't be found")]
+    UserNotFound,
+}
+
+struct ErrorBody {
+    msg: String,
+}
+
+impl From<ErrorBody> for Value {
+    fn from(body: ErrorBody) -> Self {
+        json!({ "msg": body.msg })
+    }
+}
+
+impl ToResponse for PublicError {
+    type Response = ErrorBody;
+
+    fn to_response(&self) -> Self::Response {
+        ErrorBody {
+            msg: self.to_string(),
+        }
+    }
+
+    fn status_code(&self) -> u16 {
+        404
+    }
+}
+
+#[derive(Debug)]
+enum Category {
+    Users,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+async fn load_user(id: u32) -> Result<String, warp::Rejection> {
+    let err = std::io::Error::new(std::io::ErrorKind::NotFound, format!("no user {}", id));
+    let err: DetailedError<PublicError, Category> = e!(
+        err,
+        PublicError::UserNotFound,
+        Category::Users,
+        "loading the user"
+    );
+    Err(warp::reject::custom(err))
+}
+
+#[tokio::main]
+async fn main() {
+    let routes = warp::path!("users" / u32)
+        .and_then(load_user)
+        .recover(recover_rejection::<PublicError, Category>);
+
+    let response = warp::test::request().path("/users/42").reply(&routes).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.body(), r#"{"msg":"The user couldn't be found"}"#);
+    println!("{}", String::from_utf8_lossy(response.body()));
+}
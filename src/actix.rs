@@ -0,0 +1,42 @@
+//! Support for returning errors from [`actix-web`](https://docs.rs/actix-web) handlers
+
+use crate::{DetailedError, ToResponse};
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::Value;
+
+use std::fmt::{Debug, Display};
+
+/// The response is the [JSON response](DetailedError::into_json_response_parts) of the error, any
+/// status code or header which isn't valid is replaced with `500` or left out respectively
+///
+/// actix only has a reference to the error, so it can't be logged here. The errors built through
+/// the macros and constructors are logged as they're created, so there's nothing to catch up on,
+/// but one which has been [opened](DetailedError::open) has to be resolved or logged first.
+impl<Pub, Cat> ResponseError for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+    Pub::Response: Into<Value>,
+{
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.public.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let (status, headers, body) = self.json_response_parts();
+        let mut response = HttpResponse::build(
+            StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        );
+        for (name, value) in headers {
+            if let (Ok(name), Ok(value)) =
+                (HeaderName::try_from(name), HeaderValue::try_from(value))
+            {
+                response.insert_header((name, value));
+            }
+        }
+        response.json(body)
+    }
+}
@@ -0,0 +1,211 @@
+//! Periodic rollups of how many errors have been logged, for dashboards which are built on events
+//! rather than metrics.
+//!
+//! Nothing is counted until [`start_category_counts`] is called. From then on every error which is
+//! logged increments the count for its category and level, and every `interval` a single summary
+//! event is emitted with the counts since the last one (or when [`flush_category_counts`] is
+//! called).
+
+use crate::emit::{emit, Location};
+use crate::trace::field::debug;
+use crate::Level;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Write};
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How many distinct categories and levels are counted separately, any more are counted together
+/// as `other`
+const MAX_COUNTS: usize = 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicBool = AtomicBool::new(false);
+static COUNTS: [OnceLock<Count>; MAX_COUNTS] = [const { OnceLock::new() }; MAX_COUNTS];
+static OTHER: AtomicU64 = AtomicU64::new(0);
+
+/// The count of one category and level, claimed the first time they're seen
+struct Count {
+    hash: u64,
+    level: Level,
+    category: String,
+    count: AtomicU64,
+}
+
+/// Start counting the errors which are logged, flushing the counts from a background thread every
+/// `interval`
+///
+/// Only the first call starts the thread, calling this again has no effect.
+pub fn start_category_counts(interval: Duration) {
+    ENABLED.store(true, Ordering::Relaxed);
+    if STARTED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    std::thread::Builder::new()
+        .name("api_error-category-counts".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(interval);
+            flush_category_counts();
+        })
+        .expect("failed to spawn the thread flushing the category counts");
+}
+
+/// Emit a summary event with the counts since the last flush, and reset them
+///
+/// The counts are emitted as `counts`, keyed by `<category>.<level>`, along with the `total`. No
+/// event is emitted when there haven't been any errors.
+pub fn flush_category_counts() {
+    let mut counts: BTreeMap<String, u64> = COUNTS
+        .iter()
+        .filter_map(OnceLock::get)
+        .map(|count| {
+            (
+                format!("{}.{}", count.category, count.level),
+                count.count.swap(0, Ordering::Relaxed),
+            )
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect();
+    let other = OTHER.swap(0, Ordering::Relaxed);
+    if other > 0 {
+        counts.insert("other".to_string(), other);
+    }
+    if counts.is_empty() {
+        return;
+    }
+    let total: u64 = counts.values().sum();
+    let location = Location {
+        target: module_path!(),
+        file: file!(),
+        line: line!(),
+        module: module_path!(),
+    };
+    emit(
+        Level::INFO,
+        &location,
+        &[
+            ("counts", &debug(&counts)),
+            ("total", &total),
+            ("message", &"error counts by category"),
+        ],
+    );
+}
+
+/// Count an error which has been logged
+///
+/// The counts are kept in a fixed table keyed by a hash of the category, so once a category and
+/// level have been seen this is a lookup and an atomic increment, without allocating or locking.
+pub(crate) fn record(category: &impl Display, level: Level) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut hasher = HashWriter(DefaultHasher::new());
+    let _ = write!(hasher, "{}", category);
+    let hash = hasher.0.finish();
+
+    let start = hash as usize % MAX_COUNTS;
+    for i in 0..MAX_COUNTS {
+        let slot = &COUNTS[(start + i) % MAX_COUNTS];
+        let count = slot.get_or_init(|| Count {
+            hash,
+            level,
+            category: category.to_string(),
+            count: AtomicU64::new(0),
+        });
+        if count.hash == hash && count.level == level && count.is(category) {
+            count.count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+    }
+    OTHER.fetch_add(1, Ordering::Relaxed);
+}
+
+impl Count {
+    /// Whether this is the count of `category`, compared as it's written out so that it doesn't
+    /// need to be allocated
+    fn is(&self, category: &impl Display) -> bool {
+        let mut rest = CompareWriter(Some(&self.category));
+        write!(rest, "{}", category).is_ok() && rest.0 == Some("")
+    }
+}
+
+struct HashWriter(DefaultHasher);
+
+impl Write for HashWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Consumes the expected string as it's written, it's `None` (and the write fails) once it
+/// differs
+struct CompareWriter<'a>(Option<&'a str>);
+
+impl Write for CompareWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.0.and_then(|rest| rest.strip_prefix(s)) {
+            Some(rest) => {
+                self.0 = Some(rest);
+                Ok(())
+            }
+            None => {
+                self.0 = None;
+                Err(fmt::Error)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    #[test]
+    fn the_counts_are_flushed_by_category() {
+        let _serial = serial();
+        // Long enough that the background thread never flushes during the test
+        start_category_counts(Duration::from_secs(3600));
+        start_category_counts(Duration::from_secs(3600));
+        flush_category_counts();
+
+        for category in ["Aggregated", "Aggregated", "Counted"] {
+            let io = io_error();
+            let _err: DetailedError<PublicError, &str> =
+                crate::w!(io, PublicError::NotFound, category);
+        }
+        let _err: DetailedError<PublicError, &str> =
+            DetailedError::msg("missing", PublicError::NotFound, "Aggregated");
+        let (_, events) = capture(flush_category_counts);
+
+        assert_eq!(events.len(), 1);
+        let counts = events[0].field("counts").unwrap();
+        assert!(counts.contains(r#""Aggregated.ERROR": 1"#), "{}", counts);
+        assert!(counts.contains(r#""Aggregated.WARN": 2"#), "{}", counts);
+        assert!(counts.contains(r#""Counted.WARN": 1"#), "{}", counts);
+
+        let (_, events) = capture(flush_category_counts);
+        assert!(events
+            .iter()
+            .all(|event| !event.field("counts").unwrap().contains("Aggregated")));
+    }
+
+    #[test]
+    fn categories_are_compared_as_they_are_written() {
+        let count = Count {
+            hash: 0,
+            level: Level::WARN,
+            category: "Users".to_string(),
+            count: AtomicU64::new(0),
+        };
+        assert!(count.is(&"Users"));
+        assert!(!count.is(&"User"));
+        assert!(!count.is(&"Users2"));
+        assert!(!count.is(&"Other"));
+    }
+}
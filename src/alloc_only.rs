@@ -0,0 +1,386 @@
+//! The [`DetailedError`] used when the `std` feature is disabled, which only relies on `alloc`.
+//!
+//! See the [crate level documentation](crate#no_std) for what isn't available in this mode.
+
+use crate::{FieldValue, Level, ToResponse};
+
+use anyhow::Error as InnerError;
+#[cfg(feature = "tracing")]
+use once_cell::race::OnceBox;
+#[cfg(feature = "tracing")]
+use tracing::callsite::{self, Callsite, Identifier};
+#[cfg(feature = "tracing")]
+use tracing::field::{debug, display, FieldSet, Value};
+#[cfg(feature = "tracing")]
+use tracing::level_filters::LevelFilter;
+#[cfg(feature = "tracing")]
+use tracing::metadata::Kind;
+#[cfg(feature = "tracing")]
+use tracing::subscriber::Interest;
+#[cfg(feature = "tracing")]
+use tracing::{Event, Metadata};
+
+#[cfg(feature = "tracing")]
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+#[cfg(feature = "tracing")]
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "tracing")]
+use alloc::string::ToString;
+#[cfg(feature = "tracing")]
+use alloc::vec::Vec;
+use core::error::Error as CoreError;
+use core::fmt::{self, Debug, Display};
+use core::ops::Deref;
+use core::panic::Location;
+#[cfg(feature = "tracing")]
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+pub struct DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    pub private: InnerError,
+    pub public: Pub,
+    category: Cat,
+    level: Level,
+    file: &'static str,
+    line: u32,
+    fields: BTreeMap<String, FieldValue>,
+    has_logged: bool,
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Create a new error, recording where it was created from
+    ///
+    /// Unlike with `std`, the error isn't logged until [`DetailedError::log`] is called.
+    #[track_caller]
+    pub fn new<P: CoreError + Send + Sync + 'static>(
+        private: P,
+        public: Pub,
+        category: Cat,
+        level: Level,
+    ) -> Self {
+        let location = Location::caller();
+        DetailedError {
+            private: InnerError::new(private),
+            public,
+            category,
+            level,
+            file: location.file(),
+            line: location.line(),
+            fields: BTreeMap::new(),
+            has_logged: false,
+        }
+    }
+
+    /// Wrap the private error with some additional context
+    pub fn context<C>(mut self, ctx: C) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        self.private = self.private.context(ctx);
+        self
+    }
+
+    /// Add a field to be emitted alongside the error, replacing any existing field with that key
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<FieldValue>) -> Self {
+        self.fields.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn category(&self) -> &Cat {
+        &self.category
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn file(&self) -> &str {
+        self.file
+    }
+
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    pub fn to_response(&self) -> Pub::Response {
+        self.public.to_response()
+    }
+
+    pub fn into_inner(self) -> (InnerError, Pub) {
+        (self.private, self.public)
+    }
+
+    /// Emit an event for the error, this only has an effect the first time it's called
+    ///
+    /// Without the `tracing` feature nothing is emitted.
+    pub fn log(&mut self) {
+        if self.has_logged {
+            return;
+        }
+        #[cfg(feature = "tracing")]
+        self.emit();
+        self.has_logged = true;
+    }
+
+    /// The same event as with `std`, other than the fields which rely on `std` (eg. the error id)
+    #[cfg(feature = "tracing")]
+    fn emit(&self) {
+        if self.level > LevelFilter::current() {
+            return;
+        }
+        // Skip the first entry, which is going to go into the message
+        let errors: Vec<String> = self
+            .private
+            .chain()
+            .skip(1)
+            .map(|e| e.to_string())
+            .collect();
+        let errors = debug(&errors);
+        let public_error = debug(&self.public);
+        let category = display(&self.category);
+        let message = display(&self.private);
+
+        let field_names: Vec<String> = self
+            .fields
+            .keys()
+            .map(|key| {
+                if EVENT_FIELDS.contains(&key.as_str()) {
+                    format!("additional_context.{}", key)
+                } else {
+                    key.clone()
+                }
+            })
+            .collect();
+        let mut names: Vec<&str> = Vec::with_capacity(EVENT_FIELDS.len() + self.fields.len());
+        let mut values: Vec<&dyn Value> = Vec::with_capacity(names.capacity());
+        names.extend(["errors", "public_error", "category"]);
+        values.extend([&errors as &dyn Value, &public_error, &category]);
+        for (name, value) in field_names.iter().zip(self.fields.values()) {
+            names.push(name);
+            values.push(match value {
+                FieldValue::Str(v) => v,
+                FieldValue::I64(v) => v,
+                FieldValue::U64(v) => v,
+                FieldValue::F64(v) => v,
+                FieldValue::Bool(v) => v,
+            });
+        }
+        names.extend(["file", "line", "message"]);
+        values.extend([&self.file as &dyn Value, &self.line, &message]);
+
+        match callsite(self.level, self.file, self.line, &names) {
+            Some(callsite) => dispatch(callsite, &values),
+            None => {
+                // Out of callsites, so the fields are folded into one like the overflow events
+                // of `std`
+                let fields = debug(&self.fields);
+                let values: [&dyn Value; 7] = [
+                    &errors,
+                    &public_error,
+                    &category,
+                    &fields,
+                    &self.file,
+                    &self.line,
+                    &message,
+                ];
+                if let Some(callsite) = callsite(self.level, "", 0, &OVERFLOW_FIELDS) {
+                    dispatch(callsite, &values);
+                }
+            }
+        }
+    }
+}
+
+/// The fields which are always emitted, any additional field with one of these names is emitted
+/// under `additional_context` instead
+#[cfg(feature = "tracing")]
+const EVENT_FIELDS: [&str; 6] = [
+    "errors",
+    "public_error",
+    "category",
+    "file",
+    "line",
+    "message",
+];
+
+#[cfg(feature = "tracing")]
+const OVERFLOW_FIELDS: [&str; 7] = [
+    "errors",
+    "public_error",
+    "category",
+    "fields",
+    "file",
+    "line",
+    "message",
+];
+
+/// How many callsites can be created, the last one is kept for the overflow events
+#[cfg(feature = "tracing")]
+const MAX_CALLSITES: usize = 64;
+
+/// A callsite for every distinct place and set of field names events are emitted with, as with
+/// `std` (see `emit.rs`). There's no lock without `std`, so the callsites are claimed from a fixed
+/// number of slots.
+#[cfg(feature = "tracing")]
+static CALLSITES: [OnceBox<DynamicCallsite>; MAX_CALLSITES] =
+    [const { OnceBox::new() }; MAX_CALLSITES];
+
+#[cfg(feature = "tracing")]
+struct DynamicCallsite {
+    level: Level,
+    file: &'static str,
+    line: u32,
+    names: &'static [&'static str],
+    metadata: OnceBox<Metadata<'static>>,
+    registered: AtomicBool,
+    never: AtomicU8,
+}
+
+#[cfg(feature = "tracing")]
+impl Callsite for DynamicCallsite {
+    fn set_interest(&self, interest: Interest) {
+        self.never
+            .store(u8::from(interest.is_never()), Ordering::Relaxed);
+    }
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata
+            .get()
+            .expect("the metadata is created before the callsite is registered")
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl DynamicCallsite {
+    fn new(level: Level, file: &str, line: u32, names: &[&str]) -> Self {
+        let leak = |s: &str| -> &'static str { Box::leak(Box::from(s)) };
+        let names: Vec<&'static str> = names.iter().map(|name| leak(name)).collect();
+        DynamicCallsite {
+            level,
+            file: leak(file),
+            line,
+            names: Box::leak(names.into_boxed_slice()),
+            metadata: OnceBox::new(),
+            registered: AtomicBool::new(false),
+            never: AtomicU8::new(0),
+        }
+    }
+
+    fn matches(&self, level: Level, file: &str, line: u32, names: &[&str]) -> bool {
+        self.level == level && self.line == line && self.file == file && self.names == names
+    }
+
+    fn init_metadata(&'static self) -> &'static Metadata<'static> {
+        self.metadata.get_or_init(|| {
+            Box::new(Metadata::new(
+                "event",
+                "api_error",
+                self.level,
+                Some(self.file).filter(|file| !file.is_empty()),
+                Some(self.line).filter(|_| !self.file.is_empty()),
+                None,
+                FieldSet::new(self.names, Identifier(self)),
+                Kind::EVENT,
+            ))
+        })
+    }
+}
+
+/// The callsite for the event, `None` once they've run out (the overflow callsite, which is
+/// `file` "", can always use the last slot)
+#[cfg(feature = "tracing")]
+fn callsite(
+    level: Level,
+    file: &str,
+    line: u32,
+    names: &[&str],
+) -> Option<&'static DynamicCallsite> {
+    let overflow = file.is_empty();
+    let slots = if overflow {
+        &CALLSITES[..]
+    } else {
+        &CALLSITES[..MAX_CALLSITES - 1]
+    };
+    for slot in slots {
+        let callsite =
+            slot.get_or_init(|| Box::new(DynamicCallsite::new(level, file, line, names)));
+        if callsite.matches(level, file, line, names) {
+            let callsite: &'static DynamicCallsite = callsite;
+            if !callsite.registered.swap(true, Ordering::AcqRel) {
+                callsite.init_metadata();
+                callsite::register(callsite);
+            }
+            return Some(callsite);
+        }
+    }
+    None
+}
+
+#[cfg(feature = "tracing")]
+fn dispatch(callsite: &'static DynamicCallsite, values: &[&dyn Value]) {
+    if callsite.never.load(Ordering::Relaxed) == 1 {
+        return;
+    }
+    let metadata = callsite.init_metadata();
+    tracing::dispatcher::get_default(|dispatch| {
+        if !dispatch.enabled(metadata) {
+            return;
+        }
+        let values: Vec<Option<&dyn Value>> = values.iter().map(|value| Some(*value)).collect();
+        dispatch.event(&Event::new(
+            metadata,
+            &metadata.fields().value_set_all(&values),
+        ));
+    });
+}
+
+impl<Pub, Cat> fmt::Debug for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.category)
+    }
+}
+
+impl<Pub, Cat> fmt::Display for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.private)
+    }
+}
+
+impl<Pub, Cat> CoreError for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn source(&self) -> Option<&(dyn CoreError + 'static)> {
+        self.private.source()
+    }
+}
+
+impl<P, Cat> Deref for DetailedError<P, Cat>
+where
+    Cat: Display,
+    P: ToResponse + Debug,
+{
+    type Target = InnerError;
+
+    fn deref(&self) -> &Self::Target {
+        &self.private
+    }
+}
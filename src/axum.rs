@@ -0,0 +1,202 @@
+//! Support for returning errors from [`axum`](https://docs.rs/axum) handlers
+
+use crate::{DetailedError, ToResponse};
+
+use axum_core::body::Body;
+use axum_core::response::{IntoResponse, Response};
+use http::header::CONTENT_TYPE;
+use http::{HeaderName, HeaderValue, StatusCode};
+use serde_json::Value;
+
+use std::fmt::{Debug, Display};
+
+/// The response is the [JSON response](DetailedError::into_json_response_parts) of the error, any
+/// status code or header which isn't valid is replaced with `500` or left out respectively
+///
+/// The error is logged first if it hasn't been already.
+impl<Pub, Cat> IntoResponse for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+    Pub::Response: Into<Value>,
+{
+    fn into_response(mut self) -> Response {
+        self.log();
+        let (status, headers, body) = self.into_json_response_parts();
+        let mut response = build_response(status, headers, Body::from(body.to_string()));
+        response
+            .headers_mut()
+            .entry(CONTENT_TYPE)
+            .or_insert(HeaderValue::from_static("application/json"));
+        response
+    }
+}
+
+fn build_response(status: u16, headers: Vec<(String, String)>, body: Body) -> Response {
+    let mut response = Response::new(body);
+    *response.status_mut() =
+        StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+#[cfg(feature = "futures")]
+mod stream {
+    use super::build_response;
+    use crate::{DetailedError, ToResponseStream};
+
+    use axum_core::body::Body;
+    use axum_core::response::Response;
+    use bytes::Bytes;
+    use futures_core::Stream;
+
+    use std::convert::Infallible;
+    use std::fmt::{Debug, Display};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    impl<Pub, Cat> DetailedError<Pub, Cat>
+    where
+        Cat: Display,
+        Pub: ToResponseStream + Debug,
+    {
+        /// The same as the [`IntoResponse`](axum_core::response::IntoResponse) impl, with the body
+        /// produced incrementally by the public error's [`ToResponseStream`] impl rather than
+        /// built up front
+        ///
+        /// The status code and headers are those of the public error, the body isn't passed
+        /// through the [response transform](crate::set_response_transform) as it's never held
+        /// as a whole.
+        pub fn into_stream_response(mut self) -> Response {
+            self.log();
+            let body = Body::from_stream(Chunks(Box::pin(self.public.to_response_stream())));
+            let status = self.public.status_code();
+            build_response(status, self.public.headers(), body)
+        }
+    }
+
+    struct Chunks<S>(Pin<Box<S>>);
+
+    impl<S: Stream<Item = Bytes>> Stream for Chunks<S> {
+        type Item = Result<Bytes, Infallible>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.0.as_mut().poll_next(cx).map(|chunk| chunk.map(Ok))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{io_error, serial};
+    use crate::{DetailedError, ErrorCode, ToResponse};
+
+    use axum_core::response::{IntoResponse, Response};
+    use bytes::Bytes;
+    use http::header::CONTENT_TYPE;
+    use http::StatusCode;
+    use http_body_util::BodyExt;
+    use serde_json::{json, Value};
+
+    #[derive(Debug)]
+    struct Unavailable;
+
+    impl ToResponse for Unavailable {
+        type Response = Value;
+
+        fn to_response(&self) -> Self::Response {
+            json!({ "msg": "try again later" })
+        }
+
+        fn status_code(&self) -> u16 {
+            503
+        }
+
+        fn error_code(&self) -> Option<ErrorCode> {
+            ErrorCode::new("UNAVAILABLE").ok()
+        }
+
+        fn headers(&self) -> Vec<(String, String)> {
+            vec![("Retry-After".to_string(), "5".to_string())]
+        }
+    }
+
+    fn collect(response: Response) -> (StatusCode, Bytes) {
+        let status = response.status();
+        let body = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(response.into_body().collect())
+            .unwrap()
+            .to_bytes();
+        (status, body)
+    }
+
+    fn body(err: DetailedError<Unavailable, &'static str>) -> (StatusCode, Value) {
+        let (status, body) = collect(err.into_response());
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn responds_with_the_json_response() {
+        let _serial = serial();
+        let io = io_error();
+        let err = crate::w!(io, Unavailable, "Users", "loading");
+        let (status, body) = body(err);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            body,
+            json!({ "msg": "try again later", "code": "UNAVAILABLE" })
+        );
+    }
+
+    #[test]
+    fn includes_the_headers() {
+        let _serial = serial();
+        let io = io_error();
+        let err: DetailedError<Unavailable, &'static str> =
+            crate::w!(io, Unavailable, "Users", "loading");
+        let response = err.into_response();
+        assert_eq!(response.headers()["retry-after"], "5");
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+    }
+
+    #[test]
+    fn applies_the_response_transform() {
+        let _serial = serial();
+        crate::set_response_transform(|_, headers, body| (502, headers, json!({ "error": body })));
+        let io = io_error();
+        let err = crate::w!(io, Unavailable, "Users", "loading");
+        let (status, body) = body(err);
+        crate::clear_response_transform();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert_eq!(
+            body,
+            json!({ "error": { "msg": "try again later", "code": "UNAVAILABLE" } })
+        );
+    }
+
+    #[cfg(feature = "futures")]
+    impl crate::ToResponseStream for Unavailable {
+        fn to_response_stream(
+            &self,
+        ) -> impl futures_core::Stream<Item = Bytes> + Send + 'static + use<> {
+            crate::buffered_stream("try again later")
+        }
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn streams_the_response() {
+        let _serial = serial();
+        let io = io_error();
+        let err: DetailedError<Unavailable, &'static str> =
+            crate::w!(io, Unavailable, "Users", "loading");
+        let (status, body) = collect(err.into_stream_response());
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, "try again later");
+    }
+}
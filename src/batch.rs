@@ -0,0 +1,203 @@
+use crate::emit::{emit, Location};
+use crate::trace::field::{debug, Value};
+use crate::{config, DetailedError, FieldValue, ToResponse, RESERVED_FIELDS};
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Debug, Display};
+
+/// Emit a single summary event for a batch of errors, rather than an event for each of them
+///
+/// The summary is emitted at the most severe level of the errors in the batch. Any field which has
+/// the same value across every error is promoted to a field on the summary, whereas the fields
+/// which vary are listed in `varying_fields` along with how many distinct values they had.
+///
+/// Only the errors which haven't been logged yet are summarised. The macros log the errors they
+/// create straight away, so the errors of a batch should be [opened](DetailedError::open) instead
+/// otherwise they'd be emitted twice. When none of them are left nothing is emitted.
+///
+/// The errors are all considered to have been logged afterwards.
+#[track_caller]
+pub fn log_batch<Pub, Cat>(errors: &mut [DetailedError<Pub, Cat>])
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    let pending: Vec<&DetailedError<Pub, Cat>> = errors.iter().filter(|e| !e.is_logged()).collect();
+    let Some(level) = pending.iter().map(|e| e.meta.level).min() else {
+        return;
+    };
+
+    let keys: BTreeMap<&str, ()> = pending
+        .iter()
+        .flat_map(|e| e.meta.fields.keys())
+        .map(|k| (k.as_ref(), ()))
+        .collect();
+    let mut common: Vec<(&str, &FieldValue)> = Vec::new();
+    let mut varying: BTreeMap<&str, usize> = BTreeMap::new();
+    for key in keys.into_keys() {
+        let values: Vec<Option<&FieldValue>> =
+            pending.iter().map(|e| e.meta.fields.get(key)).collect();
+        match values[0] {
+            Some(first) if values.iter().all(|v| *v == Some(first)) => common.push((key, first)),
+            _ => {
+                let distinct: HashSet<String> =
+                    values.iter().flatten().map(|v| v.to_string()).collect();
+                varying.insert(key, distinct.len());
+            }
+        }
+    }
+
+    let names = config::field_names();
+    let common_names: Vec<String> = common
+        .iter()
+        .map(|(key, _)| {
+            if RESERVED_FIELDS.contains(key) || SUMMARY_FIELDS.contains(key) {
+                format!("{}.{}", names.additional_context, key)
+            } else {
+                key.to_string()
+            }
+        })
+        .collect();
+    let categories: Vec<String> = pending
+        .iter()
+        .map(|e| e.meta.category.to_string())
+        .collect();
+    let error_ids: Vec<String> = pending.iter().map(|e| e.meta.id.to_string()).collect();
+    let error_count = pending.len() as u64;
+    let categories = debug(&categories);
+    let error_ids = debug(&error_ids);
+    let varying_fields = debug(&varying);
+    let message = format!("{} errors occurred", error_count);
+
+    let mut fields: Vec<(&str, &dyn Value)> = Vec::with_capacity(5 + common.len());
+    fields.push(("error_count", &error_count));
+    fields.push(("categories", &categories));
+    fields.push(("error_ids", &error_ids));
+    let recorded: Vec<_> = common.iter().map(|(_, value)| value.recorded()).collect();
+    for (name, value) in common_names.iter().zip(&recorded) {
+        fields.push((name, value.as_value()));
+    }
+    if !varying.is_empty() {
+        fields.push(("varying_fields", &varying_fields));
+    }
+    fields.push(("message", &message));
+
+    let caller = std::panic::Location::caller();
+    let location = Location {
+        target: module_path!(),
+        file: caller.file(),
+        line: caller.line(),
+        module: "",
+    };
+    emit(level, &location, &fields);
+    for error in errors {
+        error.meta.has_logged.set(true);
+    }
+}
+
+/// Separate the successes from the failures of a set of independent operations, eg. validating
+/// each of the inputs of a request, so that every error can be reported rather than just the first
+///
+/// Partitioning doesn't log any of the errors, the macros have already logged theirs when they
+/// were created. Errors which were [opened](DetailedError::open) instead are left for the caller,
+/// eg. to be summarised with [`log_batch`]. Both keep the order they were in.
+pub fn partition_detailed<T, Pub, Cat>(
+    results: impl IntoIterator<Item = Result<T, DetailedError<Pub, Cat>>>,
+) -> (Vec<T>, Vec<DetailedError<Pub, Cat>>)
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    let mut oks = Vec::new();
+    let mut errs = Vec::new();
+    for result in results {
+        match result {
+            Ok(t) => oks.push(t),
+            Err(err) => errs.push(err),
+        }
+    }
+    (oks, errs)
+}
+
+const SUMMARY_FIELDS: &[&str] = &["error_count", "categories", "error_ids", "varying_fields"];
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::{log_batch, partition_detailed};
+    use crate::test_support::{capture, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    fn opened(item_id: i64) -> Error {
+        DetailedError::open(io_error(), PublicError::NotFound, "Items")
+            .with_field("request_id", "req-1")
+            .with_field("item_id", item_id)
+    }
+
+    #[test]
+    fn coalesces_the_common_fields() {
+        let _serial = serial();
+        let mut errors = vec![opened(1), opened(2), opened(3)];
+        let (_, events) = capture(|| log_batch(&mut errors));
+        assert_eq!(events.len(), 1);
+        let summary = &events[0];
+        assert_eq!(summary.field("error_count"), Some("3"));
+        assert_eq!(summary.field("request_id"), Some("req-1"));
+        assert_eq!(summary.field("item_id"), None);
+        assert_eq!(summary.field("varying_fields"), Some(r#"{"item_id": 3}"#));
+        assert!(errors.iter().all(Error::is_logged));
+    }
+
+    #[test]
+    fn only_summarises_the_errors_which_havent_been_logged() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let logged: Error =
+                crate::w!(io, PublicError::NotFound, "Items", "loading", "item_id" => 1);
+            let mut errors = vec![logged, opened(2)];
+            log_batch(&mut errors);
+            log_batch(&mut errors);
+        });
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+        let summaries: Vec<_> = events
+            .iter()
+            .filter(|event| event.field("error_count").is_some())
+            .collect();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].field("error_count"), Some("1"));
+        assert_eq!(summaries[0].field("item_id"), Some("2"));
+    }
+
+    #[test]
+    fn partitioning_keeps_the_order_without_logging() {
+        let _serial = serial();
+        let (errors, events) = capture(|| {
+            let io = io_error();
+            let logged: Error =
+                crate::w!(io, PublicError::NotFound, "Items", "loading", "item_id" => 2);
+            vec![logged, opened(4)]
+        });
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+
+        let mut errors = errors.into_iter();
+        let results = vec![
+            Ok(1),
+            Err(errors.next().unwrap()),
+            Ok(3),
+            Err(errors.next().unwrap()),
+            Ok(5),
+        ];
+        let ((oks, errs), events) = capture(|| partition_detailed(results));
+        assert!(events.is_empty());
+        assert_eq!(oks, [1, 3, 5]);
+        let item_ids: Vec<_> = errs.iter().map(|err| &err.fields()["item_id"]).collect();
+        assert_eq!(
+            item_ids,
+            [&crate::FieldValue::I64(2), &crate::FieldValue::I64(4)]
+        );
+        assert!(errs[0].is_logged());
+        assert!(!errs[1].is_logged());
+    }
+}
@@ -0,0 +1,229 @@
+//! The private error used when neither the `anyhow` nor the `eyre` feature is enabled, so that the
+//! crate can be used without either of them.
+
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::ops::Deref;
+
+type Boxed = Box<dyn StdError + Send + Sync + 'static>;
+
+/// A boxed error along with the contexts which have been added to it, providing the parts of the
+/// `anyhow::Error` API which are used by [`DetailedError`](crate::DetailedError)
+///
+/// Each context wraps the error before it, so the [chain](Self::chain) starts with the outermost
+/// context and then walks the [`source`](StdError::source)s of the boxed error, as far as it
+/// exposes them. Backtraces aren't captured, and unlike with `anyhow` an error can't be
+/// downcast to the type of one of its contexts.
+pub struct BoxedError {
+    inner: Boxed,
+}
+
+/// A context added through [`BoxedError::context`], the error it was added to is its source
+struct ContextError {
+    context: Box<dyn Display + Send + Sync + 'static>,
+    source: BoxedError,
+}
+
+impl Debug for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextError")
+            .field("context", &self.context.to_string())
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.context.fmt(f)
+    }
+}
+
+impl StdError for ContextError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&*self.source.inner)
+    }
+}
+
+/// A message which has been turned into an error through [`BoxedError::msg`]
+struct MessageError<M>(M);
+
+impl<M: Debug> Debug for MessageError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<M: Display> Display for MessageError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<M: Debug + Display> StdError for MessageError<M> {}
+
+impl BoxedError {
+    pub fn new<E>(error: E) -> Self
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        BoxedError {
+            inner: Box::new(error),
+        }
+    }
+
+    /// Create an error from a message, eg. when there's no underlying error type
+    pub fn msg<M>(message: M) -> Self
+    where
+        M: Display + Debug + Send + Sync + 'static,
+    {
+        BoxedError::new(MessageError(message))
+    }
+
+    /// Wrap the error with some additional context
+    pub fn context<C>(self, context: C) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        BoxedError::new(ContextError {
+            context: Box::new(context),
+            source: self,
+        })
+    }
+
+    /// The error followed by each of its sources, outermost first
+    pub fn chain(&self) -> Chain<'_> {
+        let mut causes = Vec::new();
+        let mut next: Option<&(dyn StdError + 'static)> = Some(&*self.inner);
+        while let Some(cause) = next {
+            causes.push(cause);
+            next = cause.source();
+        }
+        Chain {
+            causes: causes.into_iter(),
+        }
+    }
+
+    /// The last error in the [chain](Self::chain)
+    pub fn root_cause(&self) -> &(dyn StdError + 'static) {
+        self.chain()
+            .last()
+            .expect("the chain always starts with the error itself")
+    }
+
+    /// Borrow the error, or the one any contexts have been added to, as `E`
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        let mut current = self;
+        loop {
+            if let Some(error) = current.inner.downcast_ref::<E>() {
+                return Some(error);
+            }
+            current = &current.inner.downcast_ref::<ContextError>()?.source;
+        }
+    }
+
+    /// Take back the error, or the one any contexts have been added to, as `E`, returning the
+    /// error as it was when it's a different type
+    pub fn downcast<E>(self) -> Result<E, Self>
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        let inner = match self.inner.downcast::<E>() {
+            Ok(error) => return Ok(*error),
+            Err(inner) => inner,
+        };
+        match inner.downcast::<ContextError>() {
+            Ok(context) => {
+                let ContextError { context, source } = *context;
+                source
+                    .downcast()
+                    .map_err(|source| BoxedError::new(ContextError { context, source }))
+            }
+            Err(inner) => Err(BoxedError { inner }),
+        }
+    }
+}
+
+impl<E> From<E> for BoxedError
+where
+    E: StdError + Send + Sync + 'static,
+{
+    fn from(error: E) -> Self {
+        BoxedError::new(error)
+    }
+}
+
+impl From<BoxedError> for Box<dyn StdError + Send + Sync + 'static> {
+    fn from(error: BoxedError) -> Self {
+        error.inner
+    }
+}
+
+impl Deref for BoxedError {
+    type Target = dyn StdError + Send + Sync + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.inner
+    }
+}
+
+/// With `{:#}` each of the causes is included, separated by `: `
+impl Display for BoxedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return Display::fmt(&self.inner, f);
+        }
+        for (i, cause) in self.chain().enumerate() {
+            if i > 0 {
+                write!(f, ": ")?;
+            }
+            write!(f, "{}", cause)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for BoxedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return Debug::fmt(&self.inner, f);
+        }
+        write!(f, "{}", self.inner)?;
+        let mut causes = self.chain().skip(1).peekable();
+        if causes.peek().is_some() {
+            write!(f, "\n\nCaused by:")?;
+            for (i, cause) in causes.enumerate() {
+                write!(f, "\n    {}: {}", i, cause)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over a [`BoxedError`] and its sources, see [`BoxedError::chain`]
+pub struct Chain<'a> {
+    causes: std::vec::IntoIter<&'a (dyn StdError + 'static)>,
+}
+
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (dyn StdError + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.causes.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.causes.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Chain<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.causes.next_back()
+    }
+}
+
+impl ExactSizeIterator for Chain<'_> {}
@@ -0,0 +1,200 @@
+use crate::{config, DetailedError, ErrorId, FieldValue, InnerError, Level, LogOnDrop, ToResponse};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display};
+use std::panic::Location;
+use std::time::Duration;
+
+/// Build a [`DetailedError`] piece by piece, for errors which are constructed dynamically rather
+/// than at a fixed point in the code where the macros can be used
+///
+/// ```ignore
+/// let err = DetailedError::builder()
+///     .private(e)
+///     .public(PublicError::NotFound)
+///     .category(Category::Users)
+///     .level(Level::WARN)
+///     .context("loading the user")
+///     .field("user_id", 42)
+///     .build();
+/// ```
+///
+/// The level defaults to the [default level](crate::set_default_level), and the error is
+/// logged once it's built (or when it's dropped with
+/// [`build_log_on_drop`](Self::build_log_on_drop)).
+pub struct DetailedErrorBuilder<Pub, Cat> {
+    private: Option<(InnerError, &'static str)>,
+    public: Option<Pub>,
+    category: Option<Cat>,
+    level: Option<Level>,
+    contexts: Vec<String>,
+    fields: HashMap<Cow<'static, str>, FieldValue>,
+    parent_id: Option<ErrorId>,
+    retryable: bool,
+    retry_after: Option<Duration>,
+    target: Option<&'static str>,
+    #[cfg(feature = "tracing")]
+    to_span: bool,
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    pub fn builder() -> DetailedErrorBuilder<Pub, Cat> {
+        DetailedErrorBuilder {
+            private: None,
+            public: None,
+            category: None,
+            level: None,
+            contexts: Vec::new(),
+            fields: HashMap::new(),
+            parent_id: None,
+            retryable: false,
+            retry_after: None,
+            target: None,
+            #[cfg(feature = "tracing")]
+            to_span: false,
+        }
+    }
+}
+
+impl<Pub, Cat> DetailedErrorBuilder<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    pub fn private<P>(mut self, private: P) -> Self
+    where
+        P: StdError + Send + Sync + 'static,
+    {
+        self.private = Some((InnerError::new(private), std::any::type_name::<P>()));
+        self
+    }
+
+    pub fn public(mut self, public: Pub) -> Self {
+        self.public = Some(public);
+        self
+    }
+
+    pub fn category(mut self, category: Cat) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Wrap the private error with some context, each call wraps the contexts before it
+    pub fn context(mut self, ctx: impl Display) -> Self {
+        self.contexts.push(ctx.to_string());
+        self
+    }
+
+    pub fn field(mut self, key: impl Into<String>, value: impl Into<FieldValue>) -> Self {
+        self.fields.insert(Cow::Owned(key.into()), value.into());
+        self
+    }
+
+    /// Link the error to the one it was created in response to, see
+    /// [`DetailedError::with_parent`]
+    pub fn parent<P, C>(mut self, parent: &DetailedError<P, C>) -> Self
+    where
+        C: Display,
+        P: ToResponse + Debug,
+    {
+        self.parent_id = Some(parent.error_id());
+        self
+    }
+
+    /// See [`DetailedError::with_retryable`]
+    pub fn retryable(mut self, retryable: bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// See [`DetailedError::with_retry_after`]
+    pub fn retry_after(mut self, after: Duration) -> Self {
+        self.retryable = true;
+        self.retry_after = Some(after);
+        self
+    }
+
+    /// See [`DetailedError::with_target`]
+    pub fn target(mut self, target: &'static str) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Record the error onto the current span once it's built rather than emitting an event, see
+    /// [`DetailedError::log_to_span`]
+    #[cfg(feature = "tracing")]
+    pub fn to_span(mut self) -> Self {
+        self.to_span = true;
+        self
+    }
+
+    /// Create the error and log it, the location of the error is where this is called
+    ///
+    /// # Panics
+    ///
+    /// If the private error, the public error or the category haven't been set
+    #[track_caller]
+    pub fn build(self) -> DetailedError<Pub, Cat> {
+        let mut err = self.assemble(Location::caller());
+        err.log();
+        err
+    }
+
+    /// Create the error without logging it, it's logged when it's dropped instead unless it's
+    /// been logged by then, see [`DetailedError::log_on_drop`]
+    ///
+    /// # Panics
+    ///
+    /// If the private error, the public error or the category haven't been set
+    #[track_caller]
+    pub fn build_log_on_drop(self) -> LogOnDrop<Pub, Cat> {
+        self.assemble(Location::caller()).log_on_drop()
+    }
+
+    fn assemble(self, location: &'static Location<'static>) -> DetailedError<Pub, Cat> {
+        let (private, private_type) = self
+            .private
+            .expect("the private error has to be set before building a DetailedError");
+        let public = self
+            .public
+            .expect("the public error has to be set before building a DetailedError");
+        let category = self
+            .category
+            .expect("the category has to be set before building a DetailedError");
+
+        let mut err = DetailedError::from_inner(
+            private,
+            Some(private_type),
+            public,
+            category,
+            self.level.unwrap_or_else(config::default_level),
+            location.file(),
+            location.line(),
+            "",
+            self.fields,
+        );
+        err.meta.parent_id = self.parent_id;
+        err.meta.retryable = self.retryable;
+        err.meta.retry_after = self.retry_after;
+        err.meta.target = self.target;
+        for ctx in self.contexts {
+            err = err.context(ctx);
+        }
+        #[cfg(feature = "tracing")]
+        if self.to_span {
+            err.log_to_span();
+        }
+        err
+    }
+}
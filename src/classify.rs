@@ -0,0 +1,155 @@
+use crate::{config, DetailedError, ToResponse};
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display};
+use std::panic::Location;
+use std::sync::RwLock;
+
+type Classifier = Box<dyn Fn(&(dyn StdError + 'static)) -> Option<Box<dyn Any>> + Send + Sync>;
+
+static CLASSIFIERS: RwLock<Vec<Classifier>> = RwLock::new(Vec::new());
+
+/// Register how errors of type `E` are categorised by [`DetailedError::from_error_classified`],
+/// so that the policy lives in one place rather than at every call site
+///
+/// When more than one registration could match an error, the outermost error in its chain which
+/// has a registration wins, and if a type has been registered more than once (for the same
+/// category type) the first registration is used.
+pub fn register_category<E, Cat, F>(category_fn: F)
+where
+    E: StdError + 'static,
+    Cat: 'static,
+    F: Fn(&E) -> Cat + Send + Sync + 'static,
+{
+    let classifier: Classifier = Box::new(move |cause| {
+        cause
+            .downcast_ref::<E>()
+            .map(|e| Box::new(category_fn(e)) as Box<dyn Any>)
+    });
+    CLASSIFIERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(classifier);
+}
+
+fn classify<Cat: 'static>(private: &(dyn StdError + 'static)) -> Option<Cat> {
+    let classifiers = CLASSIFIERS.read().unwrap_or_else(|e| e.into_inner());
+    std::iter::successors(Some(private), |&cause| cause.source()).find_map(|cause| {
+        classifiers
+            .iter()
+            .filter_map(|classify| classify(cause))
+            .find_map(|category| category.downcast::<Cat>().ok())
+            .map(|category| *category)
+    })
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display + 'static,
+    Pub: ToResponse + Debug,
+{
+    /// Create a new error with the category [registered](register_category) for the private
+    /// error, or `fallback` if there isn't one
+    ///
+    /// The error is logged straight away at the [default level](crate::set_default_level).
+    #[track_caller]
+    pub fn from_error_classified<P>(private: P, public: Pub, fallback: Cat) -> Self
+    where
+        P: StdError + Send + Sync + 'static,
+    {
+        let category = classify(&private).unwrap_or(fallback);
+        let location = Location::caller();
+        DetailedError::new_with_tracing(
+            private,
+            public,
+            None::<&str>,
+            category,
+            config::default_level(),
+            location.file(),
+            location.line(),
+            "",
+            HashMap::with_capacity(0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, PublicError};
+
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Timeout;
+
+    impl Display for Timeout {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("timed out")
+        }
+    }
+
+    impl StdError for Timeout {}
+
+    /// Only ever classified through the error it wraps
+    #[derive(Debug)]
+    struct Query(Timeout);
+
+    impl Display for Query {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("the query failed")
+        }
+    }
+
+    impl StdError for Query {
+        fn source(&self) -> Option<&(dyn StdError + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Category {
+        Io,
+        Timeout,
+        Unknown,
+    }
+
+    impl Display for Category {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            Debug::fmt(self, f)
+        }
+    }
+
+    type Error = DetailedError<PublicError, Category>;
+
+    #[test]
+    fn each_registered_type_is_classified() {
+        register_category(|_: &std::io::Error| Category::Io);
+        register_category(|_: &Timeout| Category::Timeout);
+
+        let io: Error = DetailedError::from_error_classified(
+            io_error(),
+            PublicError::NotFound,
+            Category::Unknown,
+        );
+        let timeout: Error =
+            DetailedError::from_error_classified(Timeout, PublicError::NotFound, Category::Unknown);
+        let query: Error = DetailedError::from_error_classified(
+            Query(Timeout),
+            PublicError::NotFound,
+            Category::Unknown,
+        );
+        let unknown: Error = DetailedError::from_error_classified(
+            std::fmt::Error,
+            PublicError::NotFound,
+            Category::Unknown,
+        );
+
+        assert_eq!(io.category(), &Category::Io);
+        assert_eq!(timeout.category(), &Category::Timeout);
+        assert_eq!(query.category(), &Category::Timeout);
+        assert_eq!(unknown.category(), &Category::Unknown);
+    }
+}
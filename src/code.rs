@@ -0,0 +1,100 @@
+use alloc::string::String;
+use core::fmt::{self, Display};
+
+/// A stable code for a public error which clients can match on, eg. `USER_NOT_FOUND`
+///
+/// It has to start with an uppercase letter and only contain uppercase letters, digits and
+/// underscores, which is checked when it's created.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorCode(String);
+
+impl ErrorCode {
+    pub fn new(code: impl Into<String>) -> Result<Self, InvalidErrorCode> {
+        let code = code.into();
+        let mut chars = code.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_uppercase())
+            && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        if valid {
+            Ok(ErrorCode(code))
+        } else {
+            Err(InvalidErrorCode(code))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ErrorCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The error returned by [`ErrorCode::new`] for a malformed code, holding the code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidErrorCode(pub String);
+
+impl Display for InvalidErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` isn't a valid error code, it should match `^[A-Z][A-Z0-9_]*$`",
+            self.0
+        )
+    }
+}
+
+impl core::error::Error for InvalidErrorCode {}
+
+/// A category which has an HTTP status and a stable code for every variant
+///
+/// With the `derive` feature this can be derived for an enum, which fails to compile if any of
+/// the variants are missing either of them:
+///
+/// ```ignore
+/// #[derive(Debug, api_error::Category)]
+/// enum Category {
+///     #[category(status = 404, code = "NOT_FOUND")]
+///     NotFound,
+///     #[category(status = 500, code = "INTERNAL")]
+///     Internal,
+/// }
+/// ```
+pub trait Category {
+    fn status_code(&self) -> u16;
+
+    fn code(&self) -> ErrorCode;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_well_formed_codes_are_valid() {
+        for code in ["USER_NOT_FOUND", "E404", "X"] {
+            assert_eq!(ErrorCode::new(code).unwrap().as_str(), code);
+        }
+        for code in [
+            "",
+            "user_not_found",
+            "4XX",
+            "_PRIVATE",
+            "NOT-FOUND",
+            "NOT FOUND",
+        ] {
+            assert_eq!(
+                ErrorCode::new(code),
+                Err(InvalidErrorCode(String::from(code)))
+            );
+        }
+    }
+}
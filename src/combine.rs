@@ -0,0 +1,147 @@
+use crate::{DetailedError, InnerError, ToResponse};
+
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::panic::Location;
+
+/// The private error of a [`DetailedError`] created by [`DetailedError::combine`], holding the
+/// private errors of each of the errors which were combined
+///
+/// When it's logged each of their chains is emitted as its own group within `errors`, along with
+/// their categories as `combined_categories`. Its [`source`](StdError::source) is the first of the
+/// errors, so that anything walking the chain still sees one of the causes, the rest are only
+/// available through [`errors`](Self::errors).
+pub struct CombinedError {
+    errors: Vec<InnerError>,
+    categories: Vec<String>,
+}
+
+impl CombinedError {
+    pub fn errors(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        self.errors
+            .iter()
+            .map(|err| &**err as &(dyn StdError + 'static))
+    }
+
+    /// The categories of the errors which were combined, in the same order as
+    /// [`errors`](Self::errors)
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    /// The rendered chain of each of the errors
+    pub(crate) fn chains(&self) -> Vec<Vec<String>> {
+        self.errors
+            .iter()
+            .map(|err| err.chain().map(|cause| cause.to_string()).collect())
+            .collect()
+    }
+}
+
+impl Debug for CombinedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CombinedError")
+            .field("errors", &self.chains())
+            .field("categories", &self.categories)
+            .finish()
+    }
+}
+
+impl Display for CombinedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} errors occurred", self.errors.len())?;
+        for (i, err) in self.errors.iter().enumerate() {
+            let separator = if i == 0 { ": " } else { "; " };
+            write!(f, "{}{}", separator, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for CombinedError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.errors().next()
+    }
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Combine two errors into one, eg. when two operations which ran in parallel have both
+    /// failed, the result has a single public error and category but keeps both private errors
+    /// (see [`CombinedError`])
+    ///
+    /// The combined error is logged straight away at the most severe level of the two. It has the
+    /// fields of both (those of `self` win when they have the same key), and its location is
+    /// where this is called.
+    #[track_caller]
+    pub fn combine(self, other: DetailedError<Pub, Cat>, public: Pub, category: Cat) -> Self {
+        let location = Location::caller();
+        let level = self.meta.level.min(other.meta.level);
+        let mut fields = other.meta.fields;
+        fields.extend(self.meta.fields);
+        let combined = CombinedError {
+            errors: vec![self.private, other.private],
+            categories: vec![
+                self.meta.category.to_string(),
+                other.meta.category.to_string(),
+            ],
+        };
+        let mut err = DetailedError::from_inner(
+            InnerError::new(combined),
+            Some(std::any::type_name::<CombinedError>()),
+            public,
+            category,
+            level,
+            location.file(),
+            location.line(),
+            "",
+            fields,
+        );
+        err.log();
+        err
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::test_support::{capture, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn both_errors_are_emitted_in_one_event() {
+        let _serial = serial();
+        let users: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .context("loading users");
+        let billing: Error = DetailedError::open(std::fmt::Error, PublicError::NotFound, "Billing")
+            .context("loading invoices");
+        let (combined, events) =
+            capture(|| users.combine(billing, PublicError::NotFound, "Dashboard"));
+
+        let logged: Vec<_> = events.iter().filter(|event| event.error).collect();
+        assert_eq!(logged.len(), 1);
+        let event = logged[0];
+        assert_eq!(event.field("category"), Some("Dashboard"));
+        let errors = event.field("errors").unwrap();
+        for message in [
+            "loading users",
+            "connection reset",
+            "loading invoices",
+            "an error occurred when formatting an argument",
+        ] {
+            assert!(
+                errors.contains(message),
+                "{} is missing from {}",
+                message,
+                errors
+            );
+        }
+        let categories = event.field("combined_categories").unwrap();
+        assert!(categories.contains("Users") && categories.contains("Billing"));
+        assert!(combined.is_logged());
+    }
+}
@@ -0,0 +1,585 @@
+//! Global configuration, consulted whenever an error is logged.
+//!
+//! Everything in here is process wide and safe to set from any thread, a change only affects the
+//! events emitted after it's been made.
+
+use crate::FieldValue;
+use crate::Level;
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+type FieldFilter = Arc<dyn Fn(&str, &FieldValue) -> bool + Send + Sync>;
+
+static FIELD_FILTER: RwLock<Option<FieldFilter>> = RwLock::new(None);
+
+/// Set a predicate which every additional field has to pass to be included in the emitted event
+///
+/// This is applied on top of any [`DetailedError::retain_fields`](crate::DetailedError::retain_fields)
+/// calls, eg. to drop any `debug_` fields in production.
+pub fn set_field_filter<F>(pred: F)
+where
+    F: Fn(&str, &FieldValue) -> bool + Send + Sync + 'static,
+{
+    *FIELD_FILTER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(pred));
+}
+
+/// Remove the predicate set through [`set_field_filter`]
+pub fn clear_field_filter() {
+    *FIELD_FILTER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+pub(crate) fn field_filter() -> Option<FieldFilter> {
+    FIELD_FILTER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(feature = "serde")]
+static EXPOSE_INTERNAL: AtomicBool = AtomicBool::new(false);
+
+/// Whether the internal details of an error can be included in its response, this should only
+/// ever be turned on outside of production
+///
+/// See [`DetailedError::to_response_with_debug`](crate::DetailedError::to_response_with_debug)
+#[cfg(feature = "serde")]
+pub fn set_expose_internal(expose: bool) {
+    EXPOSE_INTERNAL.store(expose, Ordering::Relaxed);
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn expose_internal() -> bool {
+    EXPOSE_INTERNAL.load(Ordering::Relaxed)
+}
+
+static DEFAULT_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// Set the level used by the constructors which don't take one explicitly, such as
+/// [`DetailedError::msg`](crate::DetailedError::msg), this defaults to [`Level::ERROR`]
+///
+/// The macros (eg. [`e!`](crate::e)) always use their own level.
+pub fn set_default_level(level: Level) {
+    DEFAULT_LEVEL.store(level_to_u8(level), Ordering::Relaxed);
+}
+
+pub(crate) fn default_level() -> Level {
+    u8_to_level(DEFAULT_LEVEL.load(Ordering::Relaxed))
+}
+
+static CHAIN_MIN_LEVEL: AtomicU8 = AtomicU8::new(4);
+
+/// Only emit the `errors` in the chain of an error for events at `level` or above, for the events
+/// below it the chain isn't walked at all. This defaults to [`Level::TRACE`], ie. every event.
+pub fn set_chain_min_level(level: Level) {
+    CHAIN_MIN_LEVEL.store(level_to_u8(level), Ordering::Relaxed);
+}
+
+pub(crate) fn chain_min_level() -> Level {
+    u8_to_level(CHAIN_MIN_LEVEL.load(Ordering::Relaxed))
+}
+
+fn level_to_u8(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        Level::TRACE => 4,
+    }
+}
+
+fn u8_to_level(level: u8) -> Level {
+    match level {
+        0 => Level::ERROR,
+        1 => Level::WARN,
+        2 => Level::INFO,
+        3 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+static NORMALIZE_PATHS: AtomicBool = AtomicBool::new(true);
+
+/// Whether the `file` an error was created in is emitted with `/` as the path separator, even
+/// when it was built on Windows, so that the paths are consistent across platforms. This is on by
+/// default.
+pub fn set_normalize_paths(normalize: bool) {
+    NORMALIZE_PATHS.store(normalize, Ordering::Relaxed);
+}
+
+pub(crate) fn normalize_paths() -> bool {
+    NORMALIZE_PATHS.load(Ordering::Relaxed)
+}
+
+static CLIENT_MESSAGE_TEMPLATE: RwLock<Cow<'static, str>> =
+    RwLock::new(Cow::Borrowed("{message} (ref: {ref})"));
+
+/// Set how [`DetailedError::client_message`](crate::DetailedError::client_message) combines the
+/// message with the error id, `{message}` and `{ref}` are replaced with each of them. This
+/// defaults to `{message} (ref: {ref})`.
+pub fn set_client_message_template(template: impl Into<String>) {
+    *CLIENT_MESSAGE_TEMPLATE
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = Cow::Owned(template.into());
+}
+
+pub(crate) fn client_message_template() -> String {
+    CLIENT_MESSAGE_TEMPLATE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .to_string()
+}
+
+static BUILD_INFO: RwLock<Option<(&'static str, &'static str)>> = RwLock::new(None);
+
+/// Set the commit the running binary was built from and when it was built, these are emitted as
+/// `build.commit` and `build.timestamp` on every event, eg. from values set by a build script:
+///
+/// ```ignore
+/// api_error::set_build_info(env!("GIT_SHA"), env!("BUILD_TIMESTAMP"));
+/// ```
+pub fn set_build_info(commit: &'static str, built_at: &'static str) {
+    *BUILD_INFO.write().unwrap_or_else(|e| e.into_inner()) = Some((commit, built_at));
+}
+
+pub(crate) fn build_info() -> Option<(&'static str, &'static str)> {
+    *BUILD_INFO.read().unwrap_or_else(|e| e.into_inner())
+}
+
+static MAX_FIELDS: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Limit how many additional fields are emitted with an error, `None` (the default) emits all of
+/// them
+///
+/// When there are more, the first `max` (sorted by key, so that the same ones are kept across
+/// events) are emitted along with how many were dropped as `fields_truncated`.
+pub fn set_max_fields(max: Option<usize>) {
+    MAX_FIELDS.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+pub(crate) fn max_fields() -> Option<usize> {
+    match MAX_FIELDS.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        max => Some(max),
+    }
+}
+
+static MESSAGE_TEMPLATE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set the template the message of every event is rendered from, rather than it being the
+/// message of the private error, eg. `[{category}] {public}: {private}`
+///
+/// The placeholders are `{category}`, `{public}` (the [`Debug`] of the public error), `{private}`
+/// and `{error_id}`, anything else is left as is. `None` goes back to the private error's message.
+pub fn set_message_template(template: Option<String>) {
+    *MESSAGE_TEMPLATE.write().unwrap_or_else(|e| e.into_inner()) = template;
+}
+
+pub(crate) fn message_template() -> Option<String> {
+    MESSAGE_TEMPLATE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Replace each `{placeholder}` in `template` with its value, in a single pass so that the values
+/// themselves are never treated as part of the template
+pub(crate) fn render_template(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let placeholder = rest[start + 1..]
+            .find('}')
+            .and_then(|end| value(&rest[start + 1..start + 1 + end]).map(|v| (v, end)));
+        match placeholder {
+            Some((v, end)) => {
+                rendered.push_str(&v);
+                rest = &rest[start + end + 2..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// The casing of the keys of the emitted fields, see [`set_key_case`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyCase {
+    /// eg. `public_error`, this is how the keys are written
+    Snake,
+    /// eg. `publicError`
+    Camel,
+    /// eg. `PublicError`
+    Pascal,
+}
+
+impl KeyCase {
+    /// Each `.` separated segment of the key is converted on its own, eg. `http.remote_ip`
+    /// becomes `http.remoteIp` in camel case
+    pub(crate) fn apply(self, key: &str) -> Cow<'_, str> {
+        if self == KeyCase::Snake || !key.contains(|c: char| c == '_' || c.is_ascii_lowercase()) {
+            return Cow::Borrowed(key);
+        }
+        let mut converted = String::with_capacity(key.len());
+        for (i, segment) in key.split('.').enumerate() {
+            if i > 0 {
+                converted.push('.');
+            }
+            let mut first_word = true;
+            for word in segment.split('_') {
+                if word.is_empty() {
+                    // Keep leading underscores, eg. `_debug`
+                    if first_word {
+                        converted.push('_');
+                    }
+                    continue;
+                }
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(c) if self == KeyCase::Pascal || !first_word => {
+                        converted.push(c.to_ascii_uppercase());
+                        converted.extend(chars);
+                    }
+                    _ => converted.push_str(word),
+                }
+                first_word = false;
+            }
+        }
+        Cow::Owned(converted)
+    }
+}
+
+static KEY_CASE: AtomicU8 = AtomicU8::new(0);
+
+/// Set the casing of the keys of every event that's emitted, and of the records rendered for the
+/// [sinks](crate::add_sink), this defaults to [`KeyCase::Snake`]
+///
+/// The `message` field is always left as it is, as are the events emitted for OpenTelemetry
+/// as their keys are defined by its semantic conventions.
+pub fn set_key_case(case: KeyCase) {
+    let case = match case {
+        KeyCase::Snake => 0,
+        KeyCase::Camel => 1,
+        KeyCase::Pascal => 2,
+    };
+    KEY_CASE.store(case, Ordering::Relaxed);
+}
+
+pub(crate) fn key_case() -> KeyCase {
+    match KEY_CASE.load(Ordering::Relaxed) {
+        0 => KeyCase::Snake,
+        1 => KeyCase::Camel,
+        _ => KeyCase::Pascal,
+    }
+}
+
+/// The keys of the main fields of the emitted events, see [`set_field_names`]
+///
+/// ```
+/// # use api_error::{set_field_names, LogFieldNames};
+/// set_field_names(LogFieldNames {
+///     category: "err_category",
+///     ..LogFieldNames::DEFAULT
+/// });
+/// # set_field_names(LogFieldNames::DEFAULT);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogFieldNames {
+    pub errors: &'static str,
+    pub public_error: &'static str,
+    pub category: &'static str,
+    /// The prefix of the additional fields which clash with one of the fields that's always
+    /// emitted
+    pub additional_context: &'static str,
+    pub file: &'static str,
+    pub line: &'static str,
+    pub module: &'static str,
+}
+
+impl LogFieldNames {
+    pub const DEFAULT: LogFieldNames = LogFieldNames {
+        errors: "errors",
+        public_error: "public_error",
+        category: "category",
+        additional_context: "additional_context",
+        file: "file",
+        line: "line",
+        module: "module",
+    };
+
+    /// Whether `key` is one of the names, so that an additional field with it is prefixed
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        [
+            self.errors,
+            self.public_error,
+            self.category,
+            self.file,
+            self.line,
+            self.module,
+        ]
+        .contains(&key)
+    }
+}
+
+impl Default for LogFieldNames {
+    fn default() -> Self {
+        LogFieldNames::DEFAULT
+    }
+}
+
+static FIELD_NAMES: RwLock<LogFieldNames> = RwLock::new(LogFieldNames::DEFAULT);
+
+/// Set the keys of the main fields of the emitted events, eg. to fit an existing log schema, this
+/// defaults to [`LogFieldNames::DEFAULT`]
+///
+/// The [key casing](set_key_case) is applied on top of these.
+pub fn set_field_names(names: LogFieldNames) {
+    *FIELD_NAMES.write().unwrap_or_else(|e| e.into_inner()) = names;
+}
+
+pub(crate) fn field_names() -> LogFieldNames {
+    *FIELD_NAMES.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// How the public error is recorded on the emitted event, see [`set_public_error_log_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PublicErrorLogMode {
+    /// Both its [`Debug`] representation as `public_error` and its
+    /// [variant name](crate::ToResponse::variant_name) as `public_error_variant`
+    Full,
+    /// Only its variant name as `public_error_variant`
+    VariantOnly,
+    /// Neither, eg. when the message already describes the error well enough
+    Omit,
+}
+
+static PUBLIC_ERROR_LOG_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Set how the public error is recorded on the emitted event, this defaults to
+/// [`PublicErrorLogMode::Full`]
+///
+/// The [`Debug`] representation can be large, and is often redundant with the message.
+pub fn set_public_error_log_mode(mode: PublicErrorLogMode) {
+    let mode = match mode {
+        PublicErrorLogMode::Full => 0,
+        PublicErrorLogMode::VariantOnly => 1,
+        PublicErrorLogMode::Omit => 2,
+    };
+    PUBLIC_ERROR_LOG_MODE.store(mode, Ordering::Relaxed);
+}
+
+pub(crate) fn public_error_log_mode() -> PublicErrorLogMode {
+    match PUBLIC_ERROR_LOG_MODE.load(Ordering::Relaxed) {
+        0 => PublicErrorLogMode::Full,
+        1 => PublicErrorLogMode::VariantOnly,
+        _ => PublicErrorLogMode::Omit,
+    }
+}
+
+#[cfg(feature = "serde")]
+type ResponseTransform = Arc<
+    dyn Fn(
+            u16,
+            Vec<(String, String)>,
+            serde_json::Value,
+        ) -> (u16, Vec<(String, String)>, serde_json::Value)
+        + Send
+        + Sync,
+>;
+
+#[cfg(feature = "serde")]
+static RESPONSE_TRANSFORM: RwLock<Option<ResponseTransform>> = RwLock::new(None);
+
+/// Set a hook which can change every response before it's returned (eg. to wrap the body in a
+/// standard envelope or echo the request id), given the status, headers and body
+///
+/// It's applied exactly once per response by
+/// [`DetailedError::into_json_response_parts`](crate::DetailedError::into_json_response_parts),
+/// which the web framework integrations are built on.
+#[cfg(feature = "serde")]
+pub fn set_response_transform<F>(transform: F)
+where
+    F: Fn(
+            u16,
+            Vec<(String, String)>,
+            serde_json::Value,
+        ) -> (u16, Vec<(String, String)>, serde_json::Value)
+        + Send
+        + Sync
+        + 'static,
+{
+    *RESPONSE_TRANSFORM
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(transform));
+}
+
+/// Remove the hook set through [`set_response_transform`]
+#[cfg(feature = "serde")]
+pub fn clear_response_transform() {
+    *RESPONSE_TRANSFORM
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn response_transform() -> Option<ResponseTransform> {
+    RESPONSE_TRANSFORM
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn the_build_info_is_only_emitted_once_set() {
+        let _serial = serial();
+        let log = || {
+            let (_, events) = capture(|| {
+                let io = io_error();
+                let _err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            });
+            events.into_iter().find(|event| event.error).unwrap()
+        };
+        let event = log();
+        assert_eq!(event.field("build.commit"), None);
+        assert_eq!(event.field("build.timestamp"), None);
+
+        set_build_info("4e75326", "2024-01-01T00:00:00Z");
+        let event = log();
+        *BUILD_INFO.write().unwrap() = None;
+        assert_eq!(event.field("build.commit"), Some("4e75326"));
+        assert_eq!(event.field("build.timestamp"), Some("2024-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn the_message_is_rendered_from_the_template() {
+        let _serial = serial();
+        set_message_template(Some(
+            "[{category}] {public}: {private} ({unknown})".to_string(),
+        ));
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let _err: Error = crate::w!(io, PublicError::NotFound, "Users");
+        });
+        set_message_template(None);
+
+        let event = events.into_iter().find(|event| event.error).unwrap();
+        assert_eq!(
+            event.field("message"),
+            Some("[Users] NotFound: connection reset ({unknown})")
+        );
+    }
+
+    #[test]
+    fn the_events_use_the_configured_field_names() {
+        let _serial = serial();
+        set_field_names(LogFieldNames {
+            category: "err_category",
+            additional_context: "ctx",
+            ..LogFieldNames::DEFAULT
+        });
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let _err: Error =
+                crate::w!(io, PublicError::NotFound, "Users", "loading", "err_category" => 1);
+        });
+        set_field_names(LogFieldNames::DEFAULT);
+
+        let event = events.into_iter().find(|event| event.error).unwrap();
+        assert_eq!(event.field("err_category"), Some("Users"));
+        assert_eq!(event.field("category"), None);
+        assert_eq!(event.field("ctx.err_category"), Some("1"));
+        assert_eq!(event.field("public_error"), Some("NotFound"));
+    }
+
+    #[test]
+    fn the_chain_is_only_emitted_at_the_minimum_level_or_above() {
+        let _serial = serial();
+        set_chain_min_level(Level::WARN);
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let _error: Error = crate::e!(io, PublicError::NotFound, "Users", "loading");
+            let io = io_error();
+            let _warn: Error = crate::w!(io, PublicError::NotFound, "Users", "loading");
+            let _info: Error = DetailedError::new(
+                io_error(),
+                PublicError::NotFound,
+                Some("loading"),
+                "Users",
+                Level::INFO,
+                file!(),
+                line!(),
+                module_path!(),
+            );
+        });
+        set_chain_min_level(Level::TRACE);
+
+        let chains: Vec<(Level, Option<&str>)> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| (event.level, event.field("errors")))
+            .collect();
+        assert_eq!(
+            chains,
+            [
+                (Level::ERROR, Some(r#"["connection reset"]"#)),
+                (Level::WARN, Some(r#"["connection reset"]"#)),
+                (Level::INFO, None),
+            ]
+        );
+    }
+
+    fn public_error_fields(mode: PublicErrorLogMode) -> (Option<String>, Option<String>) {
+        set_public_error_log_mode(mode);
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let _err: Error = crate::w!(io, PublicError::NotFound, "Users");
+        });
+        set_public_error_log_mode(PublicErrorLogMode::Full);
+        let event = events.into_iter().find(|event| event.error).unwrap();
+        (
+            event.field("public_error").map(String::from),
+            event.field("public_error_variant").map(String::from),
+        )
+    }
+
+    #[test]
+    fn the_full_public_error_is_recorded_by_default() {
+        let _serial = serial();
+        assert_eq!(
+            public_error_fields(PublicErrorLogMode::Full),
+            (Some("NotFound".to_string()), Some("NotFound".to_string()))
+        );
+    }
+
+    #[test]
+    fn only_the_variant_can_be_recorded() {
+        let _serial = serial();
+        assert_eq!(
+            public_error_fields(PublicErrorLogMode::VariantOnly),
+            (None, Some("NotFound".to_string()))
+        );
+    }
+
+    #[test]
+    fn the_public_error_can_be_left_out() {
+        let _serial = serial();
+        assert_eq!(public_error_fields(PublicErrorLogMode::Omit), (None, None));
+    }
+}
@@ -0,0 +1,98 @@
+//! A [`metrics`](https://docs.rs/metrics) counter of the errors which are logged, for dashboards
+//! built on metrics rather than events.
+//!
+//! Every error increments the counter once when it's logged, labelled with its `category` and
+//! `level`. Nothing is recorded until a recorder has been installed, eg. through
+//! `metrics-exporter-prometheus`.
+
+use crate::Level;
+
+use std::fmt::Display;
+use std::sync::RwLock;
+
+/// The name of the counter unless [`set_metrics_counter_name`] has been called
+pub const DEFAULT_METRICS_COUNTER_NAME: &str = "api_error_total";
+
+static COUNTER_NAME: RwLock<&'static str> = RwLock::new(DEFAULT_METRICS_COUNTER_NAME);
+
+/// Set the name of the counter, eg. to prefix it with the name of the service
+pub fn set_metrics_counter_name(name: &'static str) {
+    *COUNTER_NAME.write().unwrap_or_else(|e| e.into_inner()) = name;
+}
+
+pub(crate) fn record(category: &impl Display, level: Level) {
+    let name = *COUNTER_NAME.read().unwrap_or_else(|e| e.into_inner());
+    ::metrics::counter!(
+        name,
+        "category" => category.to_string(),
+        "level" => level.as_str(),
+    )
+    .increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    use metrics::{
+        Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    /// Hands out the one counter, whatever its name and labels
+    #[derive(Default)]
+    struct Count(Arc<AtomicU64>);
+
+    impl Recorder for Count {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, _: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.0.clone())
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    fn opened() -> Error {
+        DetailedError::open(io_error(), PublicError::NotFound, "Users")
+    }
+
+    #[test]
+    fn counts_each_error_once() {
+        let _serial = serial();
+        let count = Count::default();
+        metrics::with_local_recorder(&count, || {
+            let mut err = opened();
+            err.log();
+            err.log();
+            err.relog();
+            let _retry = err.clone_for_retry();
+        });
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn counts_suppressed_errors() {
+        let _serial = serial();
+        let count = Count::default();
+        metrics::with_local_recorder(&count, || {
+            let mut err = opened();
+            err.emit_if(|_| false);
+            err.relog();
+        });
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+    }
+}
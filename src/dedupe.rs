@@ -0,0 +1,169 @@
+//! Summarising recurring errors rather than logging every one of them.
+//!
+//! Once a window is set through [`set_dedupe_window`], only the first error with a given
+//! fingerprint (its category, where it was created and the messages of its chain) is logged within
+//! the window. The rest are counted, and a summary event with `count`, `first_seen` and `last_seen`
+//! is emitted either when the next one arrives after the window has passed or when
+//! [`flush_dedupe_summaries`] is called, eg. on a timer.
+
+use crate::emit::{emit, Location};
+use crate::{DetailedError, Level, ToResponse};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+static WINDOW: RwLock<Option<Duration>> = RwLock::new(None);
+static SEEN: Mutex<Option<HashMap<u64, Seen>>> = Mutex::new(None);
+
+struct Seen {
+    window_start: Instant,
+    first_seen: SystemTime,
+    last_seen: SystemTime,
+    count: u64,
+    category: String,
+    level: Level,
+    file: &'static str,
+    line: u32,
+    module: &'static str,
+}
+
+/// Only log the first error with a given fingerprint within each `window`, see the
+/// [module documentation](self) for how they're summarised, `None` logs every error again
+pub fn set_dedupe_window(window: Option<Duration>) {
+    *WINDOW.write().unwrap_or_else(|e| e.into_inner()) = window;
+}
+
+/// Emit a summary for every fingerprint which has had errors suppressed, and start new windows
+/// for all of them
+pub fn flush_dedupe_summaries() {
+    let seen = SEEN.lock().unwrap_or_else(|e| e.into_inner()).take();
+    for (fingerprint, seen) in seen.into_iter().flatten() {
+        seen.emit_summary(fingerprint);
+    }
+}
+
+/// Whether the error should be logged, or has been counted towards a summary instead
+pub(crate) fn should_log<Pub, Cat>(err: &DetailedError<Pub, Cat>) -> bool
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    let Some(window) = *WINDOW.read().unwrap_or_else(|e| e.into_inner()) else {
+        return true;
+    };
+    let fingerprint = fingerprint(err);
+    let now = SystemTime::now();
+
+    let mut seen = SEEN.lock().unwrap_or_else(|e| e.into_inner());
+    let seen = seen.get_or_insert_with(HashMap::new);
+    if let Some(entry) = seen.get_mut(&fingerprint) {
+        if entry.window_start.elapsed() < window {
+            entry.count += 1;
+            entry.last_seen = now;
+            return false;
+        }
+        if let Some(expired) = seen.remove(&fingerprint) {
+            expired.emit_summary(fingerprint);
+        }
+    }
+    seen.insert(
+        fingerprint,
+        Seen {
+            window_start: Instant::now(),
+            first_seen: now,
+            last_seen: now,
+            count: 1,
+            category: err.meta.category.to_string(),
+            level: err.meta.level,
+            file: err.meta.file,
+            line: err.meta.line,
+            module: err.meta.module,
+        },
+    );
+    true
+}
+
+/// Identifies errors which are the same, by their category, where they were created and the
+/// messages of their chain
+pub(crate) fn fingerprint<Pub, Cat>(err: &DetailedError<Pub, Cat>) -> u64
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    let mut hasher = DefaultHasher::new();
+    err.meta.category.to_string().hash(&mut hasher);
+    err.meta.file.hash(&mut hasher);
+    err.meta.line.hash(&mut hasher);
+    for cause in err.private.chain() {
+        cause.to_string().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+impl Seen {
+    /// `first_seen` and `last_seen` are emitted as milliseconds since the unix epoch
+    fn emit_summary(self, fingerprint: u64) {
+        // The first error was logged as normal, so there's nothing to summarise
+        if self.count < 2 {
+            return;
+        }
+        let unix_ms = |t: SystemTime| {
+            t.duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64)
+        };
+        let fingerprint = format!("{:016x}", fingerprint);
+        let message = format!("error occurred {} times", self.count);
+        let location = Location {
+            target: module_path!(),
+            file: self.file,
+            line: self.line,
+            module: self.module,
+        };
+        emit(
+            self.level,
+            &location,
+            &[
+                ("fingerprint", &fingerprint),
+                ("category", &self.category),
+                ("count", &self.count),
+                ("first_seen", &unix_ms(self.first_seen)),
+                ("last_seen", &unix_ms(self.last_seen)),
+                ("message", &message),
+            ],
+        );
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture, io_error, serial, PublicError};
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn recurring_errors_are_summarised() {
+        let _serial = serial();
+        set_dedupe_window(Some(Duration::from_secs(3600)));
+        let (_, events) = capture(|| {
+            for _ in 0..3 {
+                let io = io_error();
+                let _err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            }
+            flush_dedupe_summaries();
+        });
+        set_dedupe_window(None);
+
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+        let summary = events.iter().find(|event| !event.error).unwrap();
+        assert_eq!(summary.field("count"), Some("3"));
+        assert_eq!(summary.field("category"), Some("Users"));
+        assert_eq!(summary.field("message"), Some("error occurred 3 times"));
+        let ms = |name| summary.field(name).unwrap().parse::<u64>().unwrap();
+        assert!(ms("first_seen") <= ms("last_seen"));
+    }
+}
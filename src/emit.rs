@@ -0,0 +1,377 @@
+//! Emits events through the `Subscriber` API directly rather than through the `tracing` macros.
+//!
+//! The macros need the full set of field names up front, which doesn't work when the fields
+//! attached to an error are only known at runtime. Instead a callsite is built (and cached) for
+//! every distinct set of field names. Everything a callsite references has to be `'static`, so
+//! the names are interned and leaked. Keys which are only known at runtime (or a dynamic
+//! [target](crate::DetailedError::with_target)) could make that grow without bound, so both are
+//! capped. Once either is full an event which would need a new callsite is emitted through a fixed
+//! one instead, with all of its fields folded into a single `fields` value.
+//!
+//! Without the `tracing` feature nothing is emitted.
+
+#[cfg(feature = "tracing")]
+use crate::config;
+use crate::trace::field::Value;
+use crate::Level;
+
+#[cfg(feature = "tracing")]
+use tracing::callsite::{self, Callsite, Identifier};
+#[cfg(feature = "tracing")]
+use tracing::field::{Field, FieldSet, Visit};
+#[cfg(feature = "tracing")]
+use tracing::level_filters::LevelFilter;
+#[cfg(feature = "tracing")]
+use tracing::metadata::Kind;
+#[cfg(feature = "tracing")]
+use tracing::subscriber::Interest;
+#[cfg(feature = "tracing")]
+use tracing::{Event, Metadata};
+
+#[cfg(feature = "tracing")]
+use std::borrow::Cow;
+#[cfg(feature = "tracing")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "tracing")]
+use std::fmt::{Debug, Write};
+#[cfg(feature = "tracing")]
+use std::sync::atomic::{AtomicU8, Ordering};
+#[cfg(feature = "tracing")]
+use std::sync::{Mutex, OnceLock};
+
+/// How many distinct strings can be interned
+#[cfg(feature = "tracing")]
+const MAX_INTERNED: usize = 4096;
+/// How many callsites can be created, not counting those used once this is reached
+#[cfg(feature = "tracing")]
+const MAX_CALLSITES: usize = 1024;
+
+#[cfg(feature = "tracing")]
+const INTEREST_NEVER: u8 = 0;
+#[cfg(feature = "tracing")]
+const INTEREST_SOMETIMES: u8 = 1;
+
+#[cfg(feature = "tracing")]
+struct DynamicCallsite {
+    metadata: OnceLock<Metadata<'static>>,
+    interest: AtomicU8,
+}
+
+#[cfg(feature = "tracing")]
+impl Callsite for DynamicCallsite {
+    fn set_interest(&self, interest: Interest) {
+        let interest = if interest.is_never() {
+            INTEREST_NEVER
+        } else {
+            INTEREST_SOMETIMES
+        };
+        self.interest.store(interest, Ordering::Relaxed);
+    }
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.metadata
+            .get()
+            .expect("metadata is set before the callsite is registered")
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[derive(PartialEq, Eq, Hash)]
+struct CallsiteKey {
+    level: Level,
+    target: &'static str,
+    file: &'static str,
+    line: u32,
+    module: &'static str,
+    names: Vec<&'static str>,
+    error: bool,
+}
+
+/// Where an event is being emitted from
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) struct Location<'a> {
+    pub(crate) target: &'a str,
+    pub(crate) file: &'a str,
+    pub(crate) line: u32,
+    pub(crate) module: &'a str,
+}
+
+/// Give back a `'static` copy of the string, or `None` if it hasn't been seen before and there's no
+/// room left for it
+#[cfg(feature = "tracing")]
+pub(crate) fn intern(s: &str) -> Option<&'static str> {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut interned = INTERNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(s) = interned.get(s) {
+        return Some(s);
+    }
+    if interned.len() >= MAX_INTERNED {
+        return None;
+    }
+    let s: &'static str = Box::leak(s.to_owned().into_boxed_str());
+    interned.insert(s);
+    Some(s)
+}
+
+/// The callsites of the events emitted by [`DetailedError::log`](crate::DetailedError::log), so
+/// that they can be told apart from the other events emitted by the crate
+#[cfg(all(feature = "tracing", any(test, feature = "testing")))]
+static ERROR_CALLSITES: OnceLock<Mutex<HashSet<Identifier>>> = OnceLock::new();
+
+/// Whether the event was emitted by [`DetailedError::log`](crate::DetailedError::log)
+#[cfg(all(feature = "tracing", any(test, feature = "testing")))]
+pub(crate) fn is_error_event(metadata: &Metadata<'_>) -> bool {
+    ERROR_CALLSITES.get().is_some_and(|callsites| {
+        callsites
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&metadata.callsite())
+    })
+}
+
+/// The callsite for the event, `None` once the callsites (or the strings they'd need) have run out
+#[cfg(feature = "tracing")]
+fn callsite(
+    level: Level,
+    location: &Location<'_>,
+    names: &[&str],
+    error: bool,
+) -> Option<&'static DynamicCallsite> {
+    static CALLSITES: OnceLock<Mutex<HashMap<CallsiteKey, &'static DynamicCallsite>>> =
+        OnceLock::new();
+
+    let key = CallsiteKey {
+        level,
+        target: intern(location.target)?,
+        file: intern(location.file)?,
+        line: location.line,
+        module: intern(location.module)?,
+        names: names.iter().map(|n| intern(n)).collect::<Option<_>>()?,
+        error,
+    };
+    let mut callsites = CALLSITES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(callsite) = callsites.get(&key) {
+        return Some(callsite);
+    }
+    if callsites.len() >= MAX_CALLSITES {
+        return None;
+    }
+
+    let name = intern(&format!("event {}:{}", key.file, key.line))?;
+    let names: &'static [&'static str] = Box::leak(key.names.clone().into_boxed_slice());
+    let callsite = register(
+        |callsite| {
+            Metadata::new(
+                name,
+                key.target,
+                level,
+                Some(key.file),
+                Some(key.line),
+                Some(key.module).filter(|module| !module.is_empty()),
+                FieldSet::new(names, callsite),
+                Kind::EVENT,
+            )
+        },
+        error,
+    );
+    callsites.insert(key, callsite);
+    Some(callsite)
+}
+
+/// The fixed callsite used once [`callsite`] has run out, there's one for each level
+#[cfg(feature = "tracing")]
+fn overflow_callsite(level: Level, error: bool) -> &'static DynamicCallsite {
+    static CALLSITES: OnceLock<Mutex<HashMap<(Level, bool), &'static DynamicCallsite>>> =
+        OnceLock::new();
+
+    let mut callsites = CALLSITES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    callsites.entry((level, error)).or_insert_with(|| {
+        register(
+            |callsite| {
+                Metadata::new(
+                    "event",
+                    crate::LOG_TARGET,
+                    level,
+                    None,
+                    None,
+                    None,
+                    FieldSet::new(&["fields", "message"], callsite),
+                    Kind::EVENT,
+                )
+            },
+            error,
+        )
+    })
+}
+
+#[cfg(feature = "tracing")]
+fn register(
+    metadata: impl FnOnce(Identifier) -> Metadata<'static>,
+    error: bool,
+) -> &'static DynamicCallsite {
+    let callsite: &'static DynamicCallsite = Box::leak(Box::new(DynamicCallsite {
+        metadata: OnceLock::new(),
+        interest: AtomicU8::new(INTEREST_SOMETIMES),
+    }));
+    let _ = callsite.metadata.set(metadata(Identifier(callsite)));
+    #[cfg(any(test, feature = "testing"))]
+    if error {
+        ERROR_CALLSITES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(Identifier(callsite));
+    }
+    #[cfg(not(any(test, feature = "testing")))]
+    let _ = error;
+    callsite::register(callsite);
+    callsite
+}
+
+/// Emit an event with the provided fields, in the order they're provided
+#[cfg(feature = "tracing")]
+pub(crate) fn emit(level: Level, location: &Location<'_>, fields: &[(&str, &dyn Value)]) {
+    emit_event(level, location, fields, false);
+}
+
+/// Emit the event for an error, see [`emit`]
+#[cfg(feature = "tracing")]
+pub(crate) fn emit_error(level: Level, location: &Location<'_>, fields: &[(&str, &dyn Value)]) {
+    emit_event(level, location, fields, true);
+}
+
+#[cfg(feature = "tracing")]
+fn emit_event(level: Level, location: &Location<'_>, fields: &[(&str, &dyn Value)], error: bool) {
+    if level > LevelFilter::current() {
+        return;
+    }
+    let case = config::key_case();
+    #[cfg(feature = "otel")]
+    let case = if location.target == crate::OTEL_TARGET {
+        config::KeyCase::Snake
+    } else {
+        case
+    };
+    let names: Vec<Cow<'_, str>> = fields
+        .iter()
+        .map(|(name, _)| match *name {
+            "message" => Cow::Borrowed(*name),
+            name => case.apply(name),
+        })
+        .collect();
+    let names: Vec<&str> = names.iter().map(|name| name.as_ref()).collect();
+    let Some(callsite) = callsite(level, location, &names, error) else {
+        return emit_folded(level, &names, fields, error);
+    };
+    if callsite.interest.load(Ordering::Relaxed) == INTEREST_NEVER {
+        return;
+    }
+
+    let metadata = callsite.metadata();
+    tracing::dispatcher::get_default(|dispatch| {
+        if !dispatch.enabled(metadata) {
+            return;
+        }
+        let values: Vec<Option<&dyn Value>> =
+            fields.iter().map(|(_, value)| Some(*value)).collect();
+        dispatch.event(&Event::new(
+            metadata,
+            &metadata.fields().value_set_all(&values),
+        ));
+    });
+}
+
+/// Emit the event through the [overflow callsite](overflow_callsite), with every field other than
+/// the message written into `fields` as `name=value` pairs
+#[cfg(feature = "tracing")]
+fn emit_folded(level: Level, names: &[&str], fields: &[(&str, &dyn Value)], error: bool) {
+    let callsite = overflow_callsite(level, error);
+    if callsite.interest.load(Ordering::Relaxed) == INTEREST_NEVER {
+        return;
+    }
+
+    let metadata = callsite.metadata();
+    tracing::dispatcher::get_default(|dispatch| {
+        if !dispatch.enabled(metadata) {
+            return;
+        }
+        let field = metadata
+            .fields()
+            .field("fields")
+            .expect("the overflow callsite has a fields field");
+        let mut folded = Fold(String::new());
+        let mut message = None;
+        for (name, (_, value)) in names.iter().zip(fields) {
+            if *name == "message" {
+                message = Some(*value);
+                continue;
+            }
+            if !folded.0.is_empty() {
+                folded.0.push(' ');
+            }
+            let _ = write!(folded.0, "{}=", name);
+            value.record(&field, &mut folded);
+        }
+        let folded = folded.0;
+        let values: [Option<&dyn Value>; 2] = [Some(&folded), message];
+        dispatch.event(&Event::new(
+            metadata,
+            &metadata.fields().value_set_all(&values),
+        ));
+    });
+}
+
+#[cfg(feature = "tracing")]
+struct Fold(String);
+
+#[cfg(feature = "tracing")]
+impl Visit for Fold {
+    fn record_debug(&mut self, _field: &Field, value: &dyn Debug) {
+        let _ = write!(self.0, "{:?}", value);
+    }
+
+    fn record_str(&mut self, _field: &Field, value: &str) {
+        self.0.push_str(value);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn emit(_level: Level, _location: &Location<'_>, _fields: &[(&str, &dyn Value)]) {}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn emit_error(_level: Level, _location: &Location<'_>, _fields: &[(&str, &dyn Value)]) {}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::emit_folded;
+    use crate::test_support::{capture, serial};
+    use crate::trace::field::{debug, Value};
+    use crate::Level;
+
+    #[test]
+    fn folds_the_fields_into_one() {
+        let _serial = serial();
+        let tags = debug(vec!["a", "b"]);
+        let fields: [(&str, &dyn Value); 3] =
+            [("user_id", &42), ("tags", &tags), ("message", &"loading")];
+        let (_, events) =
+            capture(|| emit_folded(Level::WARN, &["userId", "tags", "message"], &fields, true));
+        assert_eq!(events.len(), 1);
+        assert!(events[0].error);
+        assert_eq!(events[0].target, crate::LOG_TARGET);
+        assert_eq!(
+            events[0].field("fields"),
+            Some(r#"userId=42 tags=["a", "b"]"#)
+        );
+        assert_eq!(events[0].field("message"), Some("loading"));
+    }
+}
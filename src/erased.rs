@@ -0,0 +1,130 @@
+use crate::{DetailedError, InnerError, ToResponse};
+
+use serde_json::Value;
+
+use std::fmt::{self, Debug, Display};
+
+/// A public error with its type erased, so that errors with different public error types can be
+/// handled through the same code path
+pub trait ErasedResponse: Send + Sync {
+    /// See [`ToResponse::status_code`]
+    fn status(&self) -> u16;
+
+    /// The response body as JSON
+    fn body(&self) -> Value;
+}
+
+impl<P> ErasedResponse for P
+where
+    P: ToResponse + Send + Sync,
+    P::Response: Into<Value>,
+{
+    fn status(&self) -> u16 {
+        self.status_code()
+    }
+
+    fn body(&self) -> Value {
+        self.to_response().into()
+    }
+}
+
+/// A [`DetailedError`] which has had both its public error and category type erased
+///
+/// See [`DetailedError::erase`]
+pub struct ErasedDetailedError {
+    pub private: InnerError,
+    pub public: Box<dyn ErasedResponse>,
+    category: String,
+}
+
+impl ErasedDetailedError {
+    pub fn status(&self) -> u16 {
+        self.public.status()
+    }
+
+    pub fn to_response(&self) -> Value {
+        self.public.body()
+    }
+
+    /// The category of the original error, rendered through its [`Display`] impl
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Erase the public error and category types, eg. so that a router can handle the errors
+    /// from all of its handlers in the same way
+    ///
+    /// The error has already been logged by this point, so it won't be emitted again.
+    pub fn erase(self) -> ErasedDetailedError
+    where
+        Pub: Send + Sync + 'static,
+        Pub::Response: Into<Value>,
+    {
+        ErasedDetailedError {
+            category: self.meta.category.to_string(),
+            private: self.private,
+            public: Box::new(self.public),
+        }
+    }
+}
+
+impl fmt::Debug for ErasedDetailedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.category)
+    }
+}
+
+impl fmt::Display for ErasedDetailedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.private)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{io_error, PublicError};
+    use crate::{DetailedError, ToResponse};
+
+    use serde_json::{json, Value};
+
+    #[derive(Debug)]
+    struct Forbidden;
+
+    impl ToResponse for Forbidden {
+        type Response = Value;
+
+        fn to_response(&self) -> Self::Response {
+            json!({ "msg": "forbidden" })
+        }
+
+        fn status_code(&self) -> u16 {
+            403
+        }
+    }
+
+    #[test]
+    fn handles_different_public_errors_the_same_way() {
+        let not_found: DetailedError<PublicError, &str> =
+            DetailedError::open(io_error(), PublicError::NotFound, "Users");
+        let forbidden: DetailedError<Forbidden, u8> = DetailedError::open(io_error(), Forbidden, 7);
+        let errors = [not_found.erase(), forbidden.erase()];
+
+        let responses: Vec<(u16, Value, &str)> = errors
+            .iter()
+            .map(|err| (err.status(), err.to_response(), err.category()))
+            .collect();
+        assert_eq!(
+            responses,
+            [
+                (404, json!("not found"), "Users"),
+                (403, json!({ "msg": "forbidden" }), "7"),
+            ]
+        );
+    }
+}
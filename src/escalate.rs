@@ -0,0 +1,102 @@
+//! Raising the level of errors which keep recurring, eg. to only page someone once a flapping
+//! dependency has failed persistently rather than the first time it does.
+
+use crate::dedupe::fingerprint;
+use crate::{DetailedError, Level, ToResponse};
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+struct Policy {
+    count: u32,
+    window: Duration,
+    to: Level,
+}
+
+static POLICY: RwLock<Option<Policy>> = RwLock::new(None);
+static SEEN: Mutex<Option<HashMap<u64, (Instant, u32)>>> = Mutex::new(None);
+
+/// Log an error at `to` (if it'd otherwise be logged at a less severe level) once there have been
+/// `fingerprint_count` errors with the same fingerprint within `window`
+///
+/// Errors have the same fingerprint when they have the same category, were created in the same
+/// place and have the same messages throughout their chain. The window starts with the first
+/// error and is restarted with the first one after it's passed.
+pub fn escalate_after(fingerprint_count: u32, window: Duration, to: Level) {
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(Policy {
+        count: fingerprint_count,
+        window,
+        to,
+    });
+    SEEN.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+/// Remove the policy set through [`escalate_after`]
+pub fn clear_escalation() {
+    POLICY.write().unwrap_or_else(|e| e.into_inner()).take();
+    SEEN.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+/// The level the error should be logged at, counting it towards the escalation policy
+pub(crate) fn level<Pub, Cat>(err: &DetailedError<Pub, Cat>) -> Level
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    let level = err.meta.level;
+    let policy = POLICY.read().unwrap_or_else(|e| e.into_inner());
+    let Some(policy) = policy.as_ref() else {
+        return level;
+    };
+
+    let mut seen = SEEN.lock().unwrap_or_else(|e| e.into_inner());
+    let (window_start, count) = seen
+        .get_or_insert_with(HashMap::new)
+        .entry(fingerprint(err))
+        .or_insert((Instant::now(), 0));
+    if window_start.elapsed() >= policy.window {
+        *window_start = Instant::now();
+        *count = 0;
+    }
+    *count = count.saturating_add(1);
+
+    // The more severe levels compare as less than the others
+    if *count >= policy.count {
+        level.min(policy.to)
+    } else {
+        level
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture, io_error, serial, PublicError};
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn recurring_errors_are_escalated_past_the_threshold() {
+        let _serial = serial();
+        escalate_after(3, Duration::from_secs(3600), Level::ERROR);
+        let (_, events) = capture(|| {
+            for _ in 0..4 {
+                let io = io_error();
+                let _err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            }
+        });
+        clear_escalation();
+
+        let levels: Vec<Level> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| event.level)
+            .collect();
+        assert_eq!(
+            levels,
+            [Level::WARN, Level::WARN, Level::ERROR, Level::ERROR]
+        );
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(feature = "std")]
+use crate::trace::field::Value;
+#[cfg(feature = "serde")]
+use crate::trace::field::{display, DisplayValue};
+
+use alloc::string::String;
+use core::fmt::{self, Display};
+
+/// The value of one of the additional fields attached to a [`DetailedError`](crate::DetailedError)
+///
+/// Numbers and booleans are kept as is, so that they're recorded as such on the emitted event
+/// rather than being turned into strings. With the `serde` feature any JSON value (eg. from
+/// `serde_json::json!`) is kept as well, it's recorded as the JSON it renders to and stays
+/// structured wherever the error is serialized, eg. in a `WireError` or a JSON sink.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Str(String),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+    #[cfg(feature = "serde")]
+    Json(serde_json::Value),
+}
+
+impl FieldValue {
+    #[cfg(feature = "std")]
+    pub(crate) fn recorded(&self) -> Recorded<'_> {
+        match self {
+            FieldValue::Str(v) => Recorded::Borrowed(v),
+            FieldValue::I64(v) => Recorded::Borrowed(v),
+            FieldValue::U64(v) => Recorded::Borrowed(v),
+            FieldValue::F64(v) => Recorded::Borrowed(v),
+            FieldValue::Bool(v) => Recorded::Borrowed(v),
+            #[cfg(feature = "serde")]
+            FieldValue::Json(v) => Recorded::Json(display(v)),
+        }
+    }
+}
+
+/// What's recorded on an event for a [`FieldValue`], the JSON values don't have a [`Value`] impl
+/// of their own so they're recorded through their [`Display`] impl
+#[cfg(feature = "std")]
+pub(crate) enum Recorded<'a> {
+    Borrowed(&'a dyn Value),
+    #[cfg(feature = "serde")]
+    Json(DisplayValue<&'a serde_json::Value>),
+}
+
+#[cfg(feature = "std")]
+impl Recorded<'_> {
+    pub(crate) fn as_value(&self) -> &dyn Value {
+        match self {
+            Recorded::Borrowed(v) => *v,
+            #[cfg(feature = "serde")]
+            Recorded::Json(v) => v,
+        }
+    }
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Str(v) => write!(f, "{}", v),
+            FieldValue::I64(v) => write!(f, "{}", v),
+            FieldValue::U64(v) => write!(f, "{}", v),
+            FieldValue::F64(v) => write!(f, "{}", v),
+            FieldValue::Bool(v) => write!(f, "{}", v),
+            #[cfg(feature = "serde")]
+            FieldValue::Json(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<&FieldValue> for serde_json::Value {
+    fn from(value: &FieldValue) -> Self {
+        match value {
+            FieldValue::Str(v) => v.as_str().into(),
+            FieldValue::I64(v) => (*v).into(),
+            FieldValue::U64(v) => (*v).into(),
+            FieldValue::F64(v) => (*v).into(),
+            FieldValue::Bool(v) => (*v).into(),
+            FieldValue::Json(v) => v.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Value> for FieldValue {
+    fn from(v: serde_json::Value) -> Self {
+        FieldValue::Json(v)
+    }
+}
+
+macro_rules! impl_from {
+    ($variant:ident: $($ty:ty),*) => {
+        $(
+            impl From<$ty> for FieldValue {
+                fn from(v: $ty) -> Self {
+                    FieldValue::$variant(v.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(Str: String, &str);
+impl_from!(I64: i8, i16, i32, i64);
+impl_from!(U64: u8, u16, u32, u64);
+impl_from!(F64: f32, f64);
+impl_from!(Bool: bool);
+
+impl From<isize> for FieldValue {
+    fn from(v: isize) -> Self {
+        FieldValue::I64(v as i64)
+    }
+}
+
+impl From<usize> for FieldValue {
+    fn from(v: usize) -> Self {
+        FieldValue::U64(v as u64)
+    }
+}
+
+/// Used by the macros to pick the [`FieldValue`] variant from the type of the value, anything
+/// which doesn't have a [`From`] impl falls back to its [`Display`] impl.
+#[doc(hidden)]
+pub mod __private {
+    use super::FieldValue;
+
+    use alloc::borrow::Cow;
+    use alloc::string::ToString;
+    use core::fmt::Display;
+
+    #[cfg(feature = "tracing")]
+    pub use tracing;
+
+    pub struct Wrap<'a, T: ?Sized>(pub &'a T);
+
+    /// Used by the macros when every key is a literal, so that string literals are borrowed
+    /// rather than allocated, any other literal (eg. a number) falls back to [`DisplayKey`]
+    pub trait StaticKey {
+        fn field_key(&self) -> Cow<'static, str>;
+    }
+
+    impl StaticKey for Wrap<'_, &'static str> {
+        fn field_key(&self) -> Cow<'static, str> {
+            Cow::Borrowed(self.0)
+        }
+    }
+
+    pub trait DisplayKey {
+        fn field_key(&self) -> Cow<'static, str>;
+    }
+
+    impl<T: Display + ?Sized> DisplayKey for &Wrap<'_, T> {
+        fn field_key(&self) -> Cow<'static, str> {
+            Cow::Owned(self.0.to_string())
+        }
+    }
+
+    pub trait ViaFrom {
+        fn field_value(&self) -> FieldValue;
+    }
+
+    impl<T: Clone + Into<FieldValue>> ViaFrom for Wrap<'_, T> {
+        fn field_value(&self) -> FieldValue {
+            self.0.clone().into()
+        }
+    }
+
+    pub trait ViaDisplay {
+        fn field_value(&self) -> FieldValue;
+    }
+
+    impl<T: Display + ?Sized> ViaDisplay for &Wrap<'_, T> {
+        fn field_value(&self) -> FieldValue {
+            FieldValue::Str(self.0.to_string())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std", feature = "tracing"))]
+mod tests {
+    use crate::test_support::{capture, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    #[test]
+    fn numbers_and_booleans_are_recorded_as_such() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let _err: DetailedError<PublicError, &str> = crate::w!(
+                io,
+                PublicError::NotFound,
+                "Users",
+                "loading",
+                "attempt" => 3,
+                "bytes" => 512u64,
+                "ratio" => 0.5,
+                "cached" => false,
+                "user" => "alice",
+            );
+        });
+        let event = &events[0];
+        assert_eq!(event.kind("attempt"), Some("i64"));
+        assert_eq!(event.field("attempt"), Some("3"));
+        assert_eq!(event.kind("bytes"), Some("u64"));
+        assert_eq!(event.kind("ratio"), Some("f64"));
+        assert_eq!(event.kind("cached"), Some("bool"));
+        assert_eq!(event.kind("user"), Some("str"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Support for returning errors from [`tonic`](https://docs.rs/tonic) services
+
+use crate::{DetailedError, ToResponse};
+
+use tonic::metadata::MetadataMap;
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+
+/// The metadata key the [`ErrorId`](crate::ErrorId) is sent under
+pub const GRPC_ERROR_ID_KEY: &str = "x-error-id";
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// The gRPC code equivalent to the [status code](ToResponse::status_code) of the public error
+    pub fn grpc_code(&self) -> Code {
+        match self.public.status_code() {
+            400 => Code::InvalidArgument,
+            401 => Code::Unauthenticated,
+            403 => Code::PermissionDenied,
+            404 => Code::NotFound,
+            409 => Code::AlreadyExists,
+            412 => Code::FailedPrecondition,
+            429 => Code::ResourceExhausted,
+            499 => Code::Cancelled,
+            501 => Code::Unimplemented,
+            503 => Code::Unavailable,
+            504 => Code::DeadlineExceeded,
+            status if (400..500).contains(&status) => Code::FailedPrecondition,
+            _ => Code::Internal,
+        }
+    }
+
+    /// Create a [`Status`] with `google.rpc` error details, so that clients get the error in a
+    /// structured form rather than just a message
+    ///
+    /// - The category is the `ErrorInfo.reason`, with the crate the error was created in as the
+    ///   `ErrorInfo.domain`
+    /// - Any [field violations](DetailedError::with_field_violation) are sent as
+    ///   `BadRequest.FieldViolation`s
+    /// - The [`ErrorId`](crate::ErrorId) is sent in the [`GRPC_ERROR_ID_KEY`] metadata
+    ///
+    /// The message is the [`Display`] of the public error, the same as when converting into a
+    /// [`Status`].
+    pub fn to_grpc_status_with_details(&self) -> Status
+    where
+        Pub: Display,
+    {
+        let domain = self.meta.module.split("::").next().unwrap_or_default();
+        let mut details =
+            ErrorDetails::with_error_info(self.meta.category.to_string(), domain, HashMap::new());
+        for (field, description) in self.field_violations() {
+            details.add_bad_request_violation(field, description);
+        }
+
+        let mut metadata = MetadataMap::new();
+        if let Ok(id) = self.meta.id.to_string().parse() {
+            metadata.insert(GRPC_ERROR_ID_KEY, id);
+        }
+        Status::with_error_details_and_metadata(
+            self.grpc_code(),
+            self.public.to_string(),
+            details,
+            metadata,
+        )
+    }
+}
+
+/// The [`grpc_code`](DetailedError::grpc_code) along with the [`Display`] of the public error as
+/// the message, the private error is never included
+///
+/// The error is logged first if it hasn't been already, and its [`ErrorId`](crate::ErrorId) is
+/// sent in the [`GRPC_ERROR_ID_KEY`] metadata.
+impl<Pub, Cat> From<DetailedError<Pub, Cat>> for Status
+where
+    Cat: Display,
+    Pub: ToResponse + Debug + Display,
+{
+    fn from(mut err: DetailedError<Pub, Cat>) -> Self {
+        err.log();
+        let mut status = Status::new(err.grpc_code(), err.public.to_string());
+        if let Ok(id) = err.meta.id.to_string().parse() {
+            status.metadata_mut().insert(GRPC_ERROR_ID_KEY, id);
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, PublicError};
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn the_details_can_be_decoded() {
+        let io = io_error();
+        let err: Error = crate::w!(io, PublicError::NotFound, "Users");
+        let err = err.with_field_violation("email", "must contain an @");
+        let status = err.to_grpc_status_with_details();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "not found");
+        assert_eq!(
+            status.metadata().get(GRPC_ERROR_ID_KEY).unwrap(),
+            err.error_id().to_string().as_str()
+        );
+
+        let details = status.get_error_details();
+        let info = details.error_info().unwrap();
+        assert_eq!(info.reason, "Users");
+        assert_eq!(info.domain, "api_error");
+        let violations = &details.bad_request().unwrap().field_violations;
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].field, "email");
+        assert_eq!(violations[0].description, "must contain an @");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn converting_logs_the_error_once() {
+        use crate::test_support::{capture, serial};
+
+        let _serial = serial();
+        let err: Error =
+            DetailedError::open(io_error(), PublicError::NotFound, "Users").context("loading");
+        let id = err.error_id();
+        let (status, events) = capture(|| Status::from(err));
+
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "not found");
+        assert_eq!(
+            status.metadata().get(GRPC_ERROR_ID_KEY).unwrap(),
+            id.to_string().as_str()
+        );
+        let logged: Vec<_> = events.iter().filter(|event| event.error).collect();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].field("error_id"), Some(id.to_string().as_str()));
+    }
+}
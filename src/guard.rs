@@ -0,0 +1,130 @@
+//! Making sure an error which was created without being logged still shows up in the logs, even
+//! when whatever was meant to handle it never does.
+
+use crate::{DetailedError, ToResponse};
+
+use std::fmt::{self, Debug, Display};
+use std::ops::{Deref, DerefMut};
+
+/// A [`DetailedError`] which is logged when it's dropped, unless it's been logged already, see
+/// [`DetailedError::log_on_drop`]
+///
+/// It can be used as the error through [`Deref`], and [`into_inner`](Self::into_inner) takes it
+/// back out once something has taken responsibility for it.
+pub struct LogOnDrop<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    err: Option<DetailedError<Pub, Cat>>,
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Log the error when it's dropped if it hasn't been logged by then, eg. one which was
+    /// [opened](Self::open) and then lost on an early return before being resolved
+    ///
+    /// This has no effect for an error which has already been logged, which is the case for those
+    /// created through the macros or [`new`](Self::new).
+    pub fn log_on_drop(self) -> LogOnDrop<Pub, Cat> {
+        LogOnDrop { err: Some(self) }
+    }
+}
+
+impl<Pub, Cat> LogOnDrop<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Take the error back out, it's no longer logged when it's dropped
+    pub fn into_inner(mut self) -> DetailedError<Pub, Cat> {
+        self.err.take().expect("the error is only taken once")
+    }
+}
+
+impl<Pub, Cat> Deref for LogOnDrop<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    type Target = DetailedError<Pub, Cat>;
+
+    fn deref(&self) -> &Self::Target {
+        self.err.as_ref().expect("the error is only taken once")
+    }
+}
+
+impl<Pub, Cat> DerefMut for LogOnDrop<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.err.as_mut().expect("the error is only taken once")
+    }
+}
+
+impl<Pub, Cat> Debug for LogOnDrop<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<Pub, Cat> Drop for LogOnDrop<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn drop(&mut self) {
+        if let Some(err) = &mut self.err {
+            err.log();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::test_support::{capture, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    fn open() -> Error {
+        DetailedError::open(io_error(), PublicError::NotFound, "Users")
+    }
+
+    fn logged(f: impl FnOnce()) -> usize {
+        let (_, events) = capture(f);
+        events.iter().filter(|event| event.error).count()
+    }
+
+    #[test]
+    fn unhandled_errors_are_logged_when_dropped() {
+        let _serial = serial();
+        let guard = open().log_on_drop();
+        assert_eq!(logged(|| drop(guard)), 1);
+        // Without the guard it's lost
+        assert_eq!(logged(|| drop(open())), 0);
+    }
+
+    #[test]
+    fn handled_errors_are_not_logged_again() {
+        let _serial = serial();
+        let mut logged_first = open().log_on_drop();
+        let taken = open().log_on_drop();
+        let count = logged(|| {
+            logged_first.log();
+            drop(logged_first);
+            let err = taken.into_inner();
+            assert!(!err.is_logged());
+        });
+        assert_eq!(count, 1);
+    }
+}
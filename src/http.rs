@@ -0,0 +1,43 @@
+use crate::Level;
+
+use std::net::IpAddr;
+
+/// The request an error occurred while handling, see [`DetailedError::with_http_context`]
+///
+/// [`DetailedError::with_http_context`]: crate::DetailedError::with_http_context
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpContext {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub remote_ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+/// The level an error with the given HTTP status should be logged at, `5xx` are [`Level::ERROR`],
+/// `4xx` are [`Level::WARN`] and anything else is [`Level::INFO`]
+///
+/// This is useful when adapting errors which only carry a status, eg. from an upstream service.
+pub fn level_from_status(status: u16) -> Level {
+    match status {
+        500.. => Level::ERROR,
+        400..=499 => Level::WARN,
+        _ => Level::INFO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_class_of_status_has_a_level() {
+        assert_eq!(level_from_status(500), Level::ERROR);
+        assert_eq!(level_from_status(503), Level::ERROR);
+        assert_eq!(level_from_status(400), Level::WARN);
+        assert_eq!(level_from_status(404), Level::WARN);
+        assert_eq!(level_from_status(499), Level::WARN);
+        assert_eq!(level_from_status(302), Level::INFO);
+        assert_eq!(level_from_status(200), Level::INFO);
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(not(feature = "uuid"))]
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Debug, Display};
+#[cfg(not(feature = "uuid"))]
+use std::hash::{BuildHasher, Hasher};
+#[cfg(not(feature = "uuid"))]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(feature = "uuid"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A unique identifier generated for every error, so that the event which is emitted can be tied
+/// back to what a client sees
+///
+/// It's displayed in the same format as a v4 UUID, and with the `uuid` feature it's generated as
+/// one and can be converted into a [`Uuid`](uuid::Uuid).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorId(u128);
+
+impl ErrorId {
+    #[cfg(feature = "uuid")]
+    pub(crate) fn new() -> Self {
+        ErrorId(uuid::Uuid::new_v4().as_u128())
+    }
+
+    #[cfg(not(feature = "uuid"))]
+    pub(crate) fn new() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        let state = RandomState::new();
+        let mut hasher = state.build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u128(nanos);
+        let high = hasher.finish();
+        hasher.write_u64(high);
+        let low = hasher.finish();
+
+        let id = ((high as u128) << 64) | low as u128;
+        // Set the version (4) and variant (RFC 4122) bits
+        let id = (id & !(0xf << 76)) | (0x4 << 76);
+        let id = (id & !(0x3 << 62)) | (0x2 << 62);
+        ErrorId(id)
+    }
+
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+
+    /// Parse an id from the format it's displayed in
+    #[cfg(feature = "serde")]
+    pub(crate) fn parse(id: &str) -> Option<Self> {
+        let hex = id.replace('-', "");
+        if hex.len() != 32 {
+            return None;
+        }
+        u128::from_str_radix(&hex, 16).ok().map(ErrorId)
+    }
+
+    #[cfg(feature = "uuid")]
+    pub fn as_uuid(&self) -> uuid::Uuid {
+        uuid::Uuid::from_u128(self.0)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<ErrorId> for uuid::Uuid {
+    fn from(id: ErrorId) -> Self {
+        id.as_uuid()
+    }
+}
+
+impl Display for ErrorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            (id >> 96) as u32,
+            (id >> 80) as u16,
+            (id >> 64) as u16,
+            (id >> 48) as u16,
+            id & 0xffff_ffff_ffff,
+        )
+    }
+}
+
+impl Debug for ErrorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ErrorId({})", self)
+    }
+}
@@ -0,0 +1,284 @@
+use crate::{config, DetailedError, ToResponse};
+
+use serde_json::{json, Value};
+
+use std::fmt::{Debug, Display};
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// The same as [`DetailedError::to_response`], with the [error code](ToResponse::error_code)
+    /// inserted as `code` (unless the body already has one). Also when
+    /// [`set_expose_internal`](crate::set_expose_internal) has been turned on a `_debug` object is
+    /// added to the body containing the private error chain, where the error was created and its
+    /// [remediation](DetailedError::with_remediation) link if it has one
+    ///
+    /// ```json
+    /// {
+    ///     "_debug": {
+    ///         "causes": [
+    ///             { "message": "failed to read my amazing file" },
+    ///             { "message": "No such file or directory (os error 2)", "type": "std::io::error::Error" }
+    ///         ],
+    ///         "location": "src/main.rs:42",
+    ///         "remediation": "https://runbooks.example.com/file-missing"
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// The type of a cause is only included where it's known, which is currently just the private
+    /// error the [`DetailedError`] was created from. Both are only added if the body is a JSON
+    /// object.
+    pub fn to_response_with_debug(&self) -> Value
+    where
+        Pub::Response: Into<Value>,
+    {
+        let mut body = self.to_response().into();
+        if let (Value::Object(map), Some(code)) = (&mut body, self.error_code()) {
+            map.entry("code")
+                .or_insert_with(|| Value::from(code.as_str()));
+        }
+        if !config::expose_internal() {
+            return body;
+        }
+        if let Value::Object(map) = &mut body {
+            let private_index = self.meta.breadcrumbs.len();
+            let causes: Vec<Value> = self
+                .private
+                .chain()
+                .enumerate()
+                .map(|(i, cause)| {
+                    let mut cause = json!({ "message": cause.to_string() });
+                    if let Some(ty) = self.meta.private_type.filter(|_| i == private_index) {
+                        cause["type"] = Value::from(ty);
+                    }
+                    cause
+                })
+                .collect();
+            let mut debug = json!({
+                "causes": causes,
+                "location": format!("{}:{}", self.meta.file, self.meta.line),
+            });
+            if let Some(remediation) = &self.meta.remediation {
+                debug["remediation"] = Value::from(remediation.as_str());
+            }
+            map.insert("_debug".to_string(), debug);
+        }
+        body
+    }
+
+    /// The response body wrapped along with the [`ErrorId`](crate::ErrorId), so that a client
+    /// can quote it and it can be matched to the event which was emitted
+    ///
+    /// ```json
+    /// { "error": { "msg": "The user couldn't be found" }, "error_id": "1b4e28ba-2fa1-41d2-883f-0016d3cca427" }
+    /// ```
+    pub fn respond_with_id(&self) -> Value
+    where
+        Pub::Response: Into<Value>,
+    {
+        json!({
+            "error": self.to_response().into(),
+            "error_id": self.meta.id.to_string(),
+        })
+    }
+
+    /// Split the response into its status code, headers and JSON body, built through
+    /// [`DetailedError::to_response_with_debug`] and then passed through the
+    /// [response transform](crate::set_response_transform) if one has been set
+    pub fn into_json_response_parts(self) -> (u16, Vec<(String, String)>, Value)
+    where
+        Pub::Response: Into<Value>,
+    {
+        self.json_response_parts()
+    }
+
+    pub(crate) fn json_response_parts(&self) -> (u16, Vec<(String, String)>, Value)
+    where
+        Pub::Response: Into<Value>,
+    {
+        let status = self.public.status_code();
+        let headers = self.public.headers();
+        let body = self.to_response_with_debug();
+        match config::response_transform() {
+            Some(transform) => transform(status, headers, body),
+            None => (status, headers, body),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{io_error, serial};
+    use crate::{DetailedError, ToResponse};
+
+    use serde_json::{json, Value};
+
+    #[derive(Debug)]
+    struct NotFound;
+
+    impl ToResponse for NotFound {
+        type Response = Value;
+
+        fn to_response(&self) -> Self::Response {
+            json!({ "msg": "not found" })
+        }
+    }
+
+    fn error() -> DetailedError<NotFound, &'static str> {
+        DetailedError::open(io_error(), NotFound, "Users")
+            .context("loading the profile")
+            .context("loading the user")
+    }
+
+    #[test]
+    fn only_exposes_the_chain_when_asked_to() {
+        let _serial = serial();
+        let err = error();
+        assert_eq!(err.to_response_with_debug(), json!({ "msg": "not found" }));
+    }
+
+    #[test]
+    fn exposes_the_chain_as_an_array() {
+        let _serial = serial();
+        let err = error();
+        crate::set_expose_internal(true);
+        let body = err.to_response_with_debug();
+        crate::set_expose_internal(false);
+        assert_eq!(body["msg"], "not found");
+        assert_eq!(
+            body["_debug"]["causes"],
+            json!([
+                { "message": "loading the user" },
+                { "message": "loading the profile" },
+                { "message": "connection reset", "type": "std::io::error::Error" },
+            ])
+        );
+        assert_eq!(
+            body["_debug"]["location"],
+            format!("{}:{}", err.file(), err.line())
+        );
+    }
+
+    #[test]
+    fn names_the_types_of_the_causes_where_known() {
+        let _serial = serial();
+        let inner = error();
+        let err: DetailedError<NotFound, &str> =
+            DetailedError::open(inner, NotFound, "Api").context("handling the request");
+        crate::set_expose_internal(true);
+        let body = err.to_response_with_debug();
+        crate::set_expose_internal(false);
+        let types: Vec<Option<&str>> = body["_debug"]["causes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|cause| cause["type"].as_str())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                None,
+                Some(std::any::type_name::<DetailedError<NotFound, &str>>()),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn the_code_is_added_unless_the_body_has_one() {
+        #[derive(Debug)]
+        struct Coded(Value);
+
+        impl ToResponse for Coded {
+            type Response = Value;
+
+            fn to_response(&self) -> Self::Response {
+                self.0.clone()
+            }
+
+            fn error_code(&self) -> Option<crate::ErrorCode> {
+                crate::ErrorCode::new("USER_NOT_FOUND").ok()
+            }
+        }
+
+        let _serial = serial();
+        let err: DetailedError<Coded, &str> =
+            DetailedError::open(io_error(), Coded(json!({ "msg": "not found" })), "Users");
+        assert_eq!(
+            err.to_response_with_debug(),
+            json!({ "msg": "not found", "code": "USER_NOT_FOUND" })
+        );
+
+        let err: DetailedError<Coded, &str> =
+            DetailedError::open(io_error(), Coded(json!({ "code": "OWN" })), "Users");
+        assert_eq!(err.to_response_with_debug(), json!({ "code": "OWN" }));
+    }
+
+    #[test]
+    fn the_response_transform_runs_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let _serial = serial();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&runs);
+        crate::set_response_transform(move |status, mut headers, mut body| {
+            counted.fetch_add(1, Ordering::Relaxed);
+            headers.push(("x-request-id".to_string(), "abc".to_string()));
+            body["request_id"] = Value::from("abc");
+            (status, headers, body)
+        });
+        let parts = error().into_json_response_parts();
+        crate::clear_response_transform();
+
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            parts,
+            (
+                500,
+                vec![("x-request-id".to_string(), "abc".to_string())],
+                json!({ "msg": "not found", "request_id": "abc" })
+            )
+        );
+        assert_eq!(
+            error().into_json_response_parts().2,
+            json!({ "msg": "not found" })
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_id_in_the_body_matches_the_emitted_event() {
+        use crate::test_support::capture;
+
+        let _serial = serial();
+        let (body, events) = capture(|| {
+            let mut err = error();
+            err.log();
+            err.respond_with_id()
+        });
+        let logged = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(body["error"], json!({ "msg": "not found" }));
+        assert_eq!(body["error_id"].as_str(), logged.field("error_id"));
+        assert!(body["error_id"].is_string());
+    }
+
+    #[test]
+    fn the_remediation_link_is_exposed_with_the_chain() {
+        let _serial = serial();
+        let err = error().with_remediation("https://runbooks.example.com/users");
+        assert_eq!(err.to_response_with_debug(), json!({ "msg": "not found" }));
+
+        crate::set_expose_internal(true);
+        let body = err.to_response_with_debug();
+        crate::set_expose_internal(false);
+        assert_eq!(
+            body["_debug"]["remediation"],
+            "https://runbooks.example.com/users"
+        );
+    }
+}
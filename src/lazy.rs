@@ -0,0 +1,170 @@
+use crate::{config, DetailedError, ErrorCode, ToResponse};
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::ops::Deref;
+use std::panic::Location;
+use std::sync::{Arc, Mutex, OnceLock};
+
+type Resolve<Pub> = Box<dyn FnOnce() -> Pub + Send>;
+
+/// A public error which is only computed the first time it's needed
+///
+/// See [`DetailedError::from_fn`]
+pub struct LazyPublic<Pub> {
+    public: OnceLock<Pub>,
+    resolve: Mutex<Option<Resolve<Pub>>>,
+}
+
+impl<Pub> LazyPublic<Pub> {
+    /// Get the public error, computing it if this is the first time it's been accessed
+    pub fn get(&self) -> &Pub {
+        self.public.get_or_init(|| {
+            let resolve = self
+                .resolve
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take()
+                .expect("the public error is only resolved once");
+            resolve()
+        })
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.public.get().is_some()
+    }
+}
+
+impl<Pub> Deref for LazyPublic<Pub> {
+    type Target = Pub;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<Pub: ToResponse> ToResponse for LazyPublic<Pub> {
+    type Response = Pub::Response;
+
+    fn to_response(&self) -> Self::Response {
+        self.get().to_response()
+    }
+
+    fn status_code(&self) -> u16 {
+        self.get().status_code()
+    }
+
+    fn headers(&self) -> Vec<(String, String)> {
+        self.get().headers()
+    }
+
+    fn error_code(&self) -> Option<ErrorCode> {
+        self.get().error_code()
+    }
+
+    /// This doesn't resolve the public error, see the [`Debug`] impl
+    fn variant_name(&self) -> Option<&'static str> {
+        self.public.get().and_then(Pub::variant_name)
+    }
+}
+
+/// This doesn't resolve the public error, so logging the error won't either
+impl<Pub: Debug> Debug for LazyPublic<Pub> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.public.get() {
+            Some(public) => public.fmt(f),
+            None => write!(f, "<unresolved>"),
+        }
+    }
+}
+
+/// Allows the private error to be shared with the function computing the public error
+pub(crate) struct SharedError<P>(pub(crate) Arc<P>);
+
+impl<P: Debug> Debug for SharedError<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<P: Display> Display for SharedError<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<P: StdError> StdError for SharedError<P> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
+
+impl<Pub, Cat> DetailedError<LazyPublic<Pub>, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Create a new error where the public error is computed from the private one, but only the
+    /// first time it's needed (eg. through [`DetailedError::to_response`])
+    ///
+    /// The error is logged straight away at the [default level](crate::set_default_level), if the
+    /// public error hasn't been computed by then it's recorded as `<unresolved>`. As the private
+    /// error is shared with `f`, it can be borrowed through [`DetailedError::downcast_ref`] but
+    /// can't be taken back with [`DetailedError::downcast`].
+    #[track_caller]
+    pub fn from_fn<P, F>(private: P, category: Cat, f: F) -> Self
+    where
+        P: StdError + Send + Sync + 'static,
+        F: FnOnce(&P) -> Pub + Send + 'static,
+    {
+        let private = Arc::new(private);
+        let shared = Arc::clone(&private);
+        let public = LazyPublic {
+            public: OnceLock::new(),
+            resolve: Mutex::new(Some(Box::new(move || f(&shared)))),
+        };
+        let location = Location::caller();
+        let mut err = DetailedError::new_with_tracing(
+            SharedError(private),
+            public,
+            None::<&str>,
+            category,
+            config::default_level(),
+            location.file(),
+            location.line(),
+            "",
+            HashMap::with_capacity(0),
+        );
+        err.meta.private_type = Some(std::any::type_name::<P>());
+        err
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn computes_the_public_error_at_most_once() {
+        let _serial = serial();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let err: DetailedError<_, &str> = DetailedError::from_fn(io_error(), "Users", move |_| {
+            counted.fetch_add(1, Ordering::Relaxed);
+            PublicError::NotFound
+        });
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        assert!(!err.public.is_resolved());
+        assert_eq!(format!("{:?}", err.public), "<unresolved>");
+
+        assert_eq!(err.to_response(), "not found");
+        assert_eq!(err.status_code(), 404);
+        assert_eq!(*err.public.get(), PublicError::NotFound);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
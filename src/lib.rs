@@ -8,7 +8,7 @@
 //!    much information as possible
 //! 2. Allowing you to provide context to the underlying error
 //! 3. Mapping that internal _private_ error into one that can be turned straight into a sanitized
-//! public response for your customer
+//!    public response for your customer
 //!
 //! Truthfully _at the moment_ it is held together by blue-tack and shoe strings. However it does
 //! work provided you conform exactly to what is expected.
@@ -19,6 +19,9 @@
 //!
 //! - This is the first argument to the macro
 //! - It must implement [`Error`](StdError) + [`Send`] + [`Sync`] + `'static`
+//! - It's held as an `anyhow::Error`, or an `eyre::Report` with the `eyre` feature. With the `std`
+//!   feature but neither of those it's a `BoxedError`, which only reports whatever sources the
+//!   boxed error exposes and doesn't capture a backtrace
 //!
 //! ### Your public error
 //!
@@ -28,11 +31,44 @@
 //! ### Context
 //!
 //! - The optional third argument is context you want to wrap your private error with
+//! - Any context is emitted as `context_breadcrumbs`, separately from the `errors` which caused it
 //!
 //! ### Additional fields
 //!
 //! - The 4th+ arguments are key + value pairs that you want to add to the tracing message that is
-//! emitted
+//!   emitted
+//! - Each pair is recorded as its own field on the event, if the key clashes with one of the fields
+//!   that's always emitted (eg. `category`) it's recorded as `additional_context.<key>` instead
+//! - Numbers and booleans are recorded as such (see [`FieldValue`]), anything else is recorded
+//!   through its [`Display`] impl
+//!
+//! ## `no_std`
+//!
+//! Disabling the default `std` feature (keeping `anyhow` and `tracing`) builds the crate with only
+//! `alloc`, for embedded/WASM targets, eg. `cargo build --no-default-features --features
+//! anyhow,tracing --target thumbv7em-none-eabihf`. In this mode [`DetailedError`] is a minimal version of itself which is
+//! built through `DetailedError::new` and then `context`/`with_field`, its location is the
+//! `&'static str` file and line of the caller and its fields are kept in a `BTreeMap`. It isn't
+//! logged until `DetailedError::log` is called, which emits a single event with the fields
+//! recorded under `additional_context`.
+//!
+//! The following are only available with `std`:
+//! - The macros ([`e!`], [`w!`], [`i!`], [`d!`], [`t!`], [`e_msg!`] and [`detailed_error!`]) and the
+//!   module path of an error
+//! - Logging at construction, and each additional field being its own field on the event
+//! - Error ids, tags, breadcrumbs, resources, deadlines, [`clone_for_retry`](DetailedError::clone_for_retry)
+//!   and [`convert_public`](DetailedError::convert_public)
+//! - [`LazyPublic`], [`log_batch`] and the global configuration (eg. [`set_field_filter`])
+//! - The `eyre`, `serde`, `futures`, `otel` and `valuable` features, which all enable `std`
+//!
+//! ## Without `tracing`
+//!
+//! Disabling the default `tracing` feature removes the dependency on `tracing`, for libraries
+//! which don't want to pull it in. The API stays the same, other than [`Level`] being a stand-in
+//! for `tracing::Level` and spans no longer being attached to errors, but no event is ever
+//! emitted. Errors are still considered to have been logged, and the [sinks](add_sink) are still
+//! called with every error when the `std` feature is enabled. The `otel`, `testing` and
+//! `valuable` features all enable `tracing`.
 //!
 //! # Examples
 //!
@@ -103,22 +139,272 @@
 //! [Debug]: std::fmt::Debug
 //! [Display]: std::fmt::Display
 
-#[cfg(feature = "anyhow")]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(feature = "std", feature = "anyhow"))]
 use anyhow::Error as InnerError;
 
 #[cfg(feature = "eyre")]
 use eyre::Report as InnerError;
 
+#[cfg(all(feature = "std", not(feature = "anyhow"), not(feature = "eyre")))]
+use boxed::BoxedError as InnerError;
+
 #[cfg(all(feature = "anyhow", feature = "eyre"))]
 compile_error!("features `anyhow` and `eyre` are mutually exclusive, please choose one");
 
-use tracing::{debug, error, info, trace, warn, Level};
+#[cfg(all(feature = "valuable", not(tracing_unstable)))]
+compile_error!("feature `valuable` requires building with `RUSTFLAGS=\"--cfg tracing_unstable\"`");
 
+#[cfg(all(not(feature = "std"), not(feature = "anyhow")))]
+compile_error!("feature `anyhow` is required when the `std` feature is disabled");
+
+#[cfg(feature = "actix-web")]
+mod actix;
+#[cfg(feature = "aggregate")]
+mod aggregate;
+#[cfg(not(feature = "std"))]
+mod alloc_only;
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(all(feature = "std", not(feature = "anyhow"), not(feature = "eyre")))]
+mod boxed;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod classify;
+mod code;
+#[cfg(feature = "std")]
+mod combine;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "metrics")]
+mod counter;
+#[cfg(feature = "std")]
+mod dedupe;
+#[cfg(feature = "std")]
+mod emit;
+#[cfg(feature = "serde")]
+mod erased;
+#[cfg(feature = "std")]
+mod escalate;
+mod field;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "std")]
+mod guard;
+#[cfg(feature = "std")]
+mod http;
+#[cfg(feature = "std")]
+mod id;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "std")]
+mod lazy;
+#[cfg(feature = "std")]
+mod lifecycle;
+#[cfg(feature = "std")]
+mod localized;
+#[cfg(not(feature = "tracing"))]
+mod no_tracing;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "opentelemetry")]
+mod otel_api;
+#[cfg(feature = "std")]
+mod panic;
+#[cfg(feature = "serde")]
+mod problem;
+#[cfg(feature = "std")]
+mod sanitize;
+#[cfg(feature = "sentry")]
+mod sentry;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "std")]
+mod sink;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(all(feature = "std", feature = "tracing"))]
+mod span;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(all(test, feature = "std"))]
+mod test_support;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+mod timing;
+#[cfg(feature = "warp")]
+mod warp;
+#[cfg(feature = "serde")]
+mod wire;
+
+#[cfg(feature = "aggregate")]
+pub use aggregate::{flush_category_counts, start_category_counts};
+#[cfg(not(feature = "std"))]
+pub use alloc_only::DetailedError;
+#[cfg(feature = "derive")]
+pub use api_error_derive::Category;
+#[cfg(feature = "std")]
+pub use batch::{log_batch, partition_detailed};
+#[cfg(all(feature = "std", not(feature = "anyhow"), not(feature = "eyre")))]
+pub use boxed::{BoxedError, Chain};
+#[cfg(feature = "std")]
+pub use builder::DetailedErrorBuilder;
+#[cfg(feature = "std")]
+pub use classify::register_category;
+pub use code::{Category, ErrorCode, InvalidErrorCode};
+#[cfg(feature = "std")]
+pub use combine::CombinedError;
+#[cfg(feature = "std")]
+pub use config::{
+    clear_field_filter, set_build_info, set_chain_min_level, set_client_message_template,
+    set_default_level, set_field_filter, set_field_names, set_key_case, set_max_fields,
+    set_message_template, set_normalize_paths, set_public_error_log_mode, KeyCase, LogFieldNames,
+    PublicErrorLogMode,
+};
+#[cfg(feature = "serde")]
+pub use config::{clear_response_transform, set_expose_internal, set_response_transform};
+#[cfg(feature = "metrics")]
+pub use counter::{set_metrics_counter_name, DEFAULT_METRICS_COUNTER_NAME};
+#[cfg(feature = "std")]
+pub use dedupe::{flush_dedupe_summaries, set_dedupe_window};
+#[cfg(feature = "serde")]
+pub use erased::{ErasedDetailedError, ErasedResponse};
+#[cfg(feature = "std")]
+pub use escalate::{clear_escalation, escalate_after};
+#[doc(hidden)]
+pub use field::__private;
+pub use field::FieldValue;
+#[cfg(feature = "grpc")]
+pub use grpc::GRPC_ERROR_ID_KEY;
+#[cfg(feature = "std")]
+pub use guard::LogOnDrop;
+#[cfg(feature = "std")]
+pub use http::{level_from_status, HttpContext};
+#[cfg(feature = "std")]
+pub use id::ErrorId;
+#[cfg(feature = "std")]
+pub use lazy::LazyPublic;
+#[cfg(feature = "std")]
+pub use localized::LocalizedResponse;
+#[cfg(not(feature = "tracing"))]
+pub use no_tracing::{Level, ParseLevelError};
+#[cfg(feature = "otel")]
+pub use otel::{set_record_otel_span_status, set_service_metadata, OtlpLogRecord, OTEL_TARGET};
+#[cfg(feature = "std")]
+pub use panic::{catch_panic, PanicError};
+#[cfg(feature = "serde")]
+pub use problem::{ProblemDetails, PROBLEM_JSON};
+#[cfg(feature = "std")]
+pub use sanitize::Sanitize;
+#[cfg(feature = "serde")]
+pub use serialize::DebugSerialize;
+#[cfg(feature = "std")]
+pub use sink::{add_sink, clear_sinks, LogRecord, SinkFormat};
+#[cfg(feature = "futures")]
+pub use stream::{buffered_stream, collect_errors, ToResponseStream};
+#[cfg(feature = "std")]
+pub use timing::time_operation;
+#[cfg(feature = "tracing")]
+pub use tracing::Level;
+#[cfg(feature = "warp")]
+pub use warp::recover_rejection;
+#[cfg(feature = "serde")]
+pub use wire::{ReconstructedError, WireError};
+
+#[cfg(feature = "std")]
+use emit::Location;
+#[cfg(feature = "std")]
+use snapshot::Snapshot;
+
+// Everything uses `tracing` through this, so that it can be swapped for the stand-ins when the
+// `tracing` feature is disabled
+#[cfg(all(feature = "std", not(feature = "tracing")))]
+use no_tracing as trace;
+#[cfg(all(feature = "std", feature = "tracing"))]
+use tracing as trace;
+
+#[cfg(feature = "std")]
+use trace::field::{debug, display, Value};
+#[cfg(all(feature = "std", feature = "tracing"))]
+use tracing::Span;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "backtrace")]
+use std::backtrace::{Backtrace, BacktraceStatus};
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
+#[cfg(feature = "std")]
 use std::error::Error as StdError;
+#[cfg(feature = "std")]
 use std::fmt::{self, Debug, Display};
+#[cfg(feature = "std")]
 use std::ops::Deref;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "backtrace")]
+use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+/// The names of the fields emitted by [`DetailedError::log`], any additional field with the same
+/// name is emitted as `additional_context.<name>` instead
+const RESERVED_FIELDS: &[&str] = &[
+    "errors",
+    "public_error",
+    "public_error_variant",
+    "category",
+    "error_id",
+    "fields_truncated",
+    "tags",
+    "context_breadcrumbs",
+    "resource.type",
+    "resource.id",
+    "http.method",
+    "http.path",
+    "http.query",
+    "http.remote_ip",
+    "http.user_agent",
+    "build.commit",
+    "build.timestamp",
+    "time_remaining_ms",
+    "deadline_exceeded",
+    "task_id",
+    "remediation_url",
+    "parent_error_id",
+    "elapsed_ms",
+    "retryable",
+    "retry_after_ms",
+    "combined_categories",
+    "os_error",
+    "backtrace",
+    "file",
+    "line",
+    "module",
+    "message",
+];
 
+#[cfg(feature = "std")]
+/// The target used for the events emitted by [`DetailedError::log`], unless the error has been
+/// given [its own](DetailedError::with_target)
+pub(crate) const LOG_TARGET: &str = module_path!();
+
+#[cfg(feature = "std")]
+/// The target used for the events emitted by [`DetailedError::emit_public_only`]
+pub const PUBLIC_TARGET: &str = "api_error::public";
+
+#[cfg(feature = "std")]
 pub struct DetailedError<Pub, Cat>
 where
     Cat: Display,
@@ -126,7 +412,8 @@ where
 {
     pub private: InnerError,
     pub public: Pub,
-    meta: Meta<Cat>,
+    // Boxed so that a `Result<T, DetailedError<..>>` stays cheap to move around
+    meta: Box<Meta<Cat>>,
 }
 
 /// This trait indicates how you want to turn your `PublicError` type into a `Response`.
@@ -136,32 +423,148 @@ pub trait ToResponse {
     type Response;
 
     fn to_response(&self) -> Self::Response;
+
+    /// The HTTP status code this error should be returned with, defaults to `500`
+    fn status_code(&self) -> u16 {
+        500
+    }
+
+    /// The stable code clients can match on for this error, see [`ErrorCode`]
+    fn error_code(&self) -> Option<ErrorCode> {
+        None
+    }
+
+    /// Any headers the response should include (eg. `Retry-After`), defaults to none
+    fn headers(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// A short, stable name for the error (eg. the name of the enum variant), emitted as
+    /// `public_error_variant`
+    ///
+    /// When this is `None` the start of the [`Debug`] representation is used instead, up to the
+    /// data the error holds.
+    fn variant_name(&self) -> Option<&'static str> {
+        None
+    }
 }
 
+#[cfg(feature = "std")]
+#[derive(Clone)]
 pub struct Meta<C> {
-    fields: HashMap<String, String>,
-    file: String,
-    module: String,
+    id: ErrorId,
+    fields: HashMap<Cow<'static, str>, FieldValue>,
+    file: &'static str,
+    module: &'static str,
     line: u32,
     level: Level,
     category: C,
-    has_logged: bool,
+    tags: Vec<String>,
+    breadcrumbs: Vec<String>,
+    deadline: Option<Instant>,
+    resource: Option<(String, String)>,
+    http: Option<HttpContext>,
+    #[cfg(feature = "tracing")]
+    span: Option<Span>,
+    violations: Vec<(String, String)>,
+    remediation: Option<String>,
+    parent_id: Option<ErrorId>,
+    retryable: bool,
+    retry_after: Option<Duration>,
+    target: Option<&'static str>,
+    elapsed: Option<Duration>,
+    #[cfg(feature = "backtrace")]
+    backtrace: Option<Arc<Backtrace>>,
+    private_type: Option<&'static str>,
+    #[cfg(feature = "tokio")]
+    task_id: Option<tokio::task::Id>,
+    #[cfg(feature = "valuable")]
+    valuable_category: Option<fn(&C) -> valuable::Value<'_>>,
+    lazy: bool,
+    has_logged: LoggedFlag,
+    // Kept apart from `has_logged` so that relogging doesn't count the error again
+    counted: LoggedFlag,
+}
+
+/// Whether the event for an error has been emitted, which can be set through a shared reference
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct LoggedFlag(AtomicBool);
+
+#[cfg(feature = "std")]
+impl LoggedFlag {
+    fn get(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set(&self, logged: bool) {
+        self.0.store(logged, Ordering::Relaxed);
+    }
 }
 
+#[cfg(feature = "std")]
+impl Clone for LoggedFlag {
+    fn clone(&self) -> Self {
+        LoggedFlag(AtomicBool::new(self.get()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C> Meta<C> {
+    fn map_category<C2>(self, f: impl FnOnce(C) -> C2) -> Meta<C2> {
+        Meta {
+            id: self.id,
+            fields: self.fields,
+            file: self.file,
+            module: self.module,
+            line: self.line,
+            level: self.level,
+            category: f(self.category),
+            tags: self.tags,
+            breadcrumbs: self.breadcrumbs,
+            deadline: self.deadline,
+            resource: self.resource,
+            http: self.http,
+            #[cfg(feature = "tracing")]
+            span: self.span,
+            violations: self.violations,
+            remediation: self.remediation,
+            parent_id: self.parent_id,
+            retryable: self.retryable,
+            retry_after: self.retry_after,
+            target: self.target,
+            elapsed: self.elapsed,
+            #[cfg(feature = "backtrace")]
+            backtrace: self.backtrace,
+            private_type: self.private_type,
+            #[cfg(feature = "tokio")]
+            task_id: self.task_id,
+            // The function is specific to the original category type
+            #[cfg(feature = "valuable")]
+            valuable_category: None,
+            lazy: self.lazy,
+            has_logged: self.has_logged,
+            counted: self.counted,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl<Pub, Cat> DetailedError<Pub, Cat>
 where
     Cat: Display,
     Pub: ToResponse + Debug,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<P: StdError + Send + Sync + 'static, C: Display + Send + Sync + 'static>(
         private: P,
         public: Pub,
         context: Option<C>,
         category: Cat,
         level: Level,
-        file: String,
+        file: &'static str,
         line: u32,
-        module: String,
+        module: &'static str,
     ) -> Self {
         Self::new_with_tracing(
             private,
@@ -186,257 +589,1590 @@ where
         context: Option<C>,
         category: Cat,
         level: Level,
-        file: String,
+        file: &'static str,
+        line: u32,
+        module: &'static str,
+        fields: HashMap<Cow<'static, str>, FieldValue>,
+    ) -> Self {
+        let mut err = Self::from_inner(
+            InnerError::new(private),
+            Some(std::any::type_name::<P>()),
+            public,
+            category,
+            level,
+            file,
+            line,
+            module,
+            fields,
+        );
+        if let Some(ctx) = context {
+            err = err.context(ctx);
+        }
+        err.log();
+        err
+    }
+
+    /// Create a new error without logging it, it's logged the first time its response is built
+    /// (through [`to_response`](Self::to_response), [`to_response_with`](Self::to_response_with),
+    /// [`into_response_parts`](Self::into_response_parts) or anything built on them), it's taken
+    /// apart with [`into_inner`](Self::into_inner) or [`log`](Self::log) is called, see
+    /// [`detailed_error_lazy!`] for the macro form
+    ///
+    /// This keeps errors which are recovered from straight away (eg. when a fallback succeeds) out
+    /// of the logs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_lazy<P: StdError + Send + Sync + 'static, C: Display + Send + Sync + 'static>(
+        private: P,
+        public: Pub,
+        context: Option<C>,
+        category: Cat,
+        level: Level,
+        file: &'static str,
+        line: u32,
+        module: &'static str,
+        fields: HashMap<Cow<'static, str>, FieldValue>,
+    ) -> Self {
+        let mut err = Self::from_inner(
+            InnerError::new(private),
+            Some(std::any::type_name::<P>()),
+            public,
+            category,
+            level,
+            file,
+            line,
+            module,
+            fields,
+        );
+        if let Some(ctx) = context {
+            err = err.context(ctx);
+        }
+        err.meta.lazy = true;
+        err
+    }
+
+    /// Create a new error from just a message, rather than an underlying error type
+    ///
+    /// This is the equivalent of `anyhow!`/`eyre!`, see [`e_msg!`] for the macro form.
+    #[track_caller]
+    pub fn msg<M: Display>(message: M, public: Pub, category: Cat) -> Self {
+        let location = std::panic::Location::caller();
+        Self::new_msg(
+            message,
+            public,
+            category,
+            config::default_level(),
+            location.file(),
+            location.line(),
+            "",
+        )
+    }
+
+    pub fn new_msg<M: Display>(
+        message: M,
+        public: Pub,
+        category: Cat,
+        level: Level,
+        file: &'static str,
+        line: u32,
+        module: &'static str,
+    ) -> Self {
+        let mut err = Self::from_inner(
+            InnerError::msg(message.to_string()),
+            None,
+            public,
+            category,
+            level,
+            file,
+            line,
+            module,
+            HashMap::with_capacity(0),
+        );
+        err.log();
+        err
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn from_inner(
+        private: InnerError,
+        private_type: Option<&'static str>,
+        public: Pub,
+        category: Cat,
+        level: Level,
+        file: &'static str,
         line: u32,
-        module: String,
-        fields: HashMap<String, String>,
+        module: &'static str,
+        fields: HashMap<Cow<'static, str>, FieldValue>,
     ) -> Self {
-        let meta = Meta {
+        let meta = Box::new(Meta {
+            id: ErrorId::new(),
             fields,
             file,
             module,
             line,
             level,
             category,
-            has_logged: false,
-        };
-        #[cfg(feature = "anyhow")]
-        let private = if let Some(ctx) = context {
-            anyhow::Error::new(private).context(ctx)
-        } else {
-            anyhow::Error::new(private)
-        };
-        #[cfg(feature = "eyre")]
-        let private = if let Some(ctx) = context {
-            eyre::Report::new(private).wrap_err(ctx)
-        } else {
-            eyre::Report::new(private)
-        };
-        let mut err = DetailedError {
+            tags: Vec::new(),
+            breadcrumbs: Vec::new(),
+            deadline: None,
+            resource: None,
+            http: None,
+            #[cfg(feature = "tracing")]
+            span: None,
+            violations: Vec::new(),
+            remediation: None,
+            parent_id: None,
+            retryable: false,
+            retry_after: None,
+            target: None,
+            elapsed: None,
+            // Only kept when capturing is enabled through `RUST_BACKTRACE`/`RUST_LIB_BACKTRACE`
+            #[cfg(feature = "backtrace")]
+            backtrace: Some(Backtrace::capture())
+                .filter(|backtrace| backtrace.status() == BacktraceStatus::Captured)
+                .map(Arc::new),
+            private_type,
+            #[cfg(feature = "tokio")]
+            task_id: tokio::task::try_id(),
+            #[cfg(feature = "valuable")]
+            valuable_category: None,
+            lazy: false,
+            has_logged: LoggedFlag::default(),
+            counted: LoggedFlag::default(),
+        });
+        DetailedError {
             public,
             private,
             meta,
-        };
-        err.log();
-        err
+        }
+    }
+
+    /// Wrap the private error with some additional context
+    ///
+    /// The contexts are tracked separately to the underlying cause of the error and are emitted as
+    /// `context_breadcrumbs`, outermost first. This only has an effect on the emitted event if
+    /// it's used before the error is logged.
+    pub fn context<C>(mut self, ctx: C) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        self.meta.breadcrumbs.insert(0, ctx.to_string());
+        #[cfg(not(feature = "eyre"))]
+        let private = self.private.context(ctx);
+        #[cfg(feature = "eyre")]
+        let private = self.private.wrap_err(ctx);
+        self.private = private;
+        self
+    }
+
+    pub fn context_breadcrumbs(&self) -> &[String] {
+        &self.meta.breadcrumbs
+    }
+
+    pub fn category(&self) -> &Cat {
+        &self.meta.category
+    }
+
+    /// The level the error is logged at, this doesn't include any
+    /// [escalation](crate::escalate_after) which is only worked out as it's logged
+    pub fn level(&self) -> Level {
+        self.meta.level
+    }
+
+    /// The file the error was created in, emitted as `file`
+    pub fn file(&self) -> &'static str {
+        self.meta.file
+    }
+
+    /// The line the error was created on, emitted as `line`
+    pub fn line(&self) -> u32 {
+        self.meta.line
+    }
+
+    /// The module the error was created in, emitted as `module`
+    ///
+    /// This is empty for the errors which aren't created through the macros (eg. through
+    /// [`DetailedError::msg`] or [`catch_panic`](crate::catch_panic)), as only the file and line
+    /// of their caller can be tracked. The `module` field is left out of their events.
+    pub fn module(&self) -> &'static str {
+        self.meta.module
+    }
+
+    /// The additional fields which are emitted along with the error
+    pub fn fields(&self) -> &HashMap<Cow<'static, str>, FieldValue> {
+        &self.meta.fields
     }
 
     pub fn to_response(&self) -> Pub::Response {
+        self.log_if_lazy();
         self.public.to_response()
     }
 
+    /// See [`ToResponse::status_code`]
+    pub fn status_code(&self) -> u16 {
+        self.public.status_code()
+    }
+
+    /// See [`ToResponse::error_code`]
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        self.public.error_code()
+    }
+
+    /// See [`ToResponse::headers`], these are applied to the responses built by the framework
+    /// integrations (eg. axum's `IntoResponse`)
+    pub fn headers(&self) -> Vec<(String, String)> {
+        self.public.headers()
+    }
+
+    /// Split the response into its status code, headers and body, for transports which assemble
+    /// the response themselves
+    pub fn into_response_parts(self) -> (u16, Vec<(String, String)>, Pub::Response) {
+        self.log_if_lazy();
+        (
+            self.public.status_code(),
+            self.public.headers(),
+            self.public.to_response(),
+        )
+    }
+
+    /// Build a response from the public error with `f` rather than its [`ToResponse`] impl, for
+    /// the odd endpoint which needs a different shape of body
+    pub fn to_response_with<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&Pub) -> T,
+    {
+        self.log_if_lazy();
+        f(&self.public)
+    }
+
     pub fn into_inner(self) -> (InnerError, Pub) {
+        self.log_if_lazy();
         (self.private, self.public)
     }
 
+    /// Get the error back after it's been converted into an [`InnerError`] (eg. through `?` in a
+    /// function returning an `anyhow::Result`), including through any context added since
+    ///
+    /// Whether the error has been logged is kept, so it's only logged again if it wasn't before.
+    pub fn recover(err: InnerError) -> Result<Self, InnerError>
+    where
+        Pub: Send + Sync + 'static,
+        Cat: Send + Sync + 'static,
+    {
+        err.downcast()
+    }
+
+    /// Whether the event for this error has been emitted
+    pub fn is_logged(&self) -> bool {
+        self.meta.has_logged.get()
+    }
+
+    /// Make sure the error has been logged and then discard everything but the public error, eg.
+    /// at the boundary where only the public error is needed to build the response
+    pub fn log_into_public(mut self) -> Pub {
+        self.log();
+        self.public
+    }
+
+    /// Transform the public error, keeping the private error and all of the metadata as is.
+    ///
+    /// As the error has already been logged, the event won't be emitted again.
+    pub fn map_public<P2, F>(self, f: F) -> DetailedError<P2, Cat>
+    where
+        P2: ToResponse + Debug,
+        F: FnOnce(Pub) -> P2,
+    {
+        DetailedError {
+            private: self.private,
+            public: f(self.public),
+            meta: self.meta,
+        }
+    }
+
+    /// Transform the category, eg. when an inner module has its own category type, keeping the
+    /// private error, public error and the rest of the metadata as is.
+    pub fn map_category<C2, F>(self, f: F) -> DetailedError<Pub, C2>
+    where
+        C2: Display,
+        F: FnOnce(Cat) -> C2,
+    {
+        DetailedError {
+            private: self.private,
+            public: self.public,
+            meta: Box::new(self.meta.map_category(f)),
+        }
+    }
+
+    /// Convert the public error into another public error type through its [`From`] impl.
+    ///
+    /// This is what allows errors to be layered, an inner module can keep its own public error
+    /// type and the outer layer only needs to provide a `From` impl for it. A blanket
+    /// `impl From<DetailedError<P1, Cat>> for DetailedError<P2, Cat>` isn't possible on stable
+    /// as it overlaps with `impl<T> From<T> for T`, so at the `?` site use
+    /// [`DetailedResultExt::convert_public`] instead.
+    ///
+    /// ```
+    /// # use api_error::{DetailedError, DetailedResultExt, ToResponse, e};
+    /// # use std::fmt;
+    /// #[derive(Debug)]
+    /// struct DbError;
+    ///
+    /// #[derive(Debug)]
+    /// enum ApiError {
+    ///     Db(DbError),
+    /// }
+    ///
+    /// impl From<DbError> for ApiError {
+    ///     fn from(e: DbError) -> Self {
+    ///         ApiError::Db(e)
+    ///     }
+    /// }
+    /// # impl ToResponse for DbError {
+    /// #     type Response = ();
+    /// #     fn to_response(&self) {}
+    /// # }
+    /// # impl ToResponse for ApiError {
+    /// #     type Response = &'static str;
+    /// #     fn to_response(&self) -> &'static str { "db" }
+    /// # }
+    /// # struct Category;
+    /// # impl fmt::Display for Category {
+    /// #     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "Category") }
+    /// # }
+    ///
+    /// fn query() -> Result<(), DetailedError<DbError, Category>> {
+    ///     std::fs::File::open("missing.db").map_err(|e| e!(e, DbError, Category, "opening db"))?;
+    ///     Ok(())
+    /// }
+    ///
+    /// fn handler() -> Result<(), DetailedError<ApiError, Category>> {
+    ///     query().convert_public::<ApiError>()?;
+    ///     Ok(())
+    /// }
+    ///
+    /// assert_eq!(handler().unwrap_err().to_response(), "db");
+    /// ```
+    pub fn convert_public<P2>(self) -> DetailedError<P2, Cat>
+    where
+        P2: ToResponse + Debug + From<Pub>,
+    {
+        self.map_public(P2::from)
+    }
+
+    /// Wrap the error in a variant of an existing top level error type, eg. one built with
+    /// `thiserror`, which has a `From` impl for it
+    ///
+    /// The error is moved as it is, so it isn't logged again and can be matched back out of the
+    /// variant later on.
+    ///
+    /// ```
+    /// # use api_error::{DetailedError, ToResponse, e};
+    /// # use std::fmt;
+    /// # #[derive(Debug)]
+    /// # struct PublicError;
+    /// # impl ToResponse for PublicError {
+    /// #     type Response = &'static str;
+    /// #     fn to_response(&self) -> &'static str { "unavailable" }
+    /// # }
+    /// # struct Category;
+    /// # impl fmt::Display for Category {
+    /// #     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { write!(f, "Category") }
+    /// # }
+    /// #[derive(Debug, thiserror::Error)]
+    /// enum AppError {
+    ///     #[error(transparent)]
+    ///     Detailed(#[from] DetailedError<PublicError, Category>),
+    ///     #[error("the config is invalid")]
+    ///     Config,
+    /// }
+    ///
+    /// let err = std::fs::File::open("missing.toml").unwrap_err();
+    /// let err: DetailedError<PublicError, Category> = e!(err, PublicError, Category, "reading config");
+    /// let app: AppError = err.into_variant();
+    ///
+    /// let AppError::Detailed(detailed) = app else {
+    ///     unreachable!()
+    /// };
+    /// assert!(detailed.is_logged());
+    /// ```
+    pub fn into_variant<T>(self) -> T
+    where
+        T: From<Self>,
+    {
+        T::from(self)
+    }
+
     #[inline]
     pub fn log(&mut self) {
+        self.log_once();
+    }
+
+    pub(crate) fn log_if_lazy(&self) {
+        if self.meta.lazy {
+            self.log_once();
+        }
+    }
+
+    /// Count the error towards the `metrics` counter and the aggregate counts, both only count an
+    /// error once and before deduplication so that they cover every error, even those whose
+    /// events are suppressed or which are [relogged](Self::relog)
+    #[cfg_attr(
+        not(any(feature = "metrics", feature = "aggregate")),
+        allow(unused_variables)
+    )]
+    fn count(&self, level: Level) {
+        if self.meta.counted.get() {
+            return;
+        }
+        self.meta.counted.set(true);
+        #[cfg(feature = "metrics")]
+        counter::record(&self.meta.category, level);
+        #[cfg(feature = "aggregate")]
+        aggregate::record(&self.meta.category, level);
+    }
+
+    /// The body of [`log`](Self::log), which only needs a shared reference so that a
+    /// [lazy](detailed_error_lazy) error can be logged when its response is built
+    fn log_once(&self) {
         let error = &self.private;
         let meta = &self.meta;
-        if self.meta.has_logged {
+        if self.meta.has_logged.get() {
+            return;
+        }
+        let level = escalate::level(self);
+        self.count(level);
+        if !dedupe::should_log(self) {
+            self.meta.has_logged.set(true);
             return;
         }
 
         let mut errors: Vec<String> = vec![];
+        let emit_chain = level <= config::chain_min_level();
 
-        // Skip the first entry, which is going to go into the msg field
-        for cause in error.chain().skip(1) {
-            errors.push(cause.to_string());
-        }
-
-        let has_fields = !meta.fields.is_empty();
-        match meta.level {
-            Level::ERROR if has_fields => {
-                error!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    additional_context = ?meta.fields,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
-            }
-            Level::ERROR => {
-                error!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
-            }
-            Level::WARN if has_fields => {
-                warn!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    additional_context = ?meta.fields,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
-            }
-            Level::WARN => {
-                warn!(
-                    errors = ?errors,
-                    category = %meta.category,
-                    public_error = ?self.public,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
+        // Skip the first entry, which is going to go into the msg field, as well as any of the
+        // contexts which are emitted as breadcrumbs
+        if emit_chain {
+            for cause in error.chain().skip(meta.breadcrumbs.len().max(1)) {
+                errors.push(cause.to_string());
             }
-            Level::INFO if has_fields => {
-                info!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    additional_context = ?meta.fields,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
+        }
+
+        let filter = config::field_filter();
+        let mut keys: Vec<&str> = meta
+            .fields
+            .iter()
+            .filter(|(key, value)| filter.as_ref().is_none_or(|f| f(key, value)))
+            .map(|(key, _)| key.as_ref())
+            .collect();
+        keys.sort();
+        let fields_truncated = config::max_fields()
+            .filter(|max| keys.len() > *max)
+            .map(|max| {
+                let truncated = (keys.len() - max) as u64;
+                keys.truncate(max);
+                truncated
+            });
+        // Each field is recorded as its own top level field on the event, unless it would clash
+        // with one of the fields that's always emitted
+        let names = config::field_names();
+        let context_names: Vec<String> = keys
+            .iter()
+            .map(|key| {
+                if RESERVED_FIELDS.contains(key) || names.contains(key) {
+                    format!("{}.{}", names.additional_context, key)
+                } else {
+                    key.to_string()
+                }
+            })
+            .collect();
+
+        let errors_field = debug(&errors);
+        // Each of the errors which were combined is emitted as its own group within `errors`
+        let combined = error.downcast_ref::<CombinedError>().filter(|_| emit_chain);
+        let combined_chains = combined.map(|combined| debug(combined.chains()));
+        let combined_categories = combined.map(|combined| debug(combined.categories()));
+        let public_error = debug(&self.public);
+        let public_error_variant = public_variant(&self.public);
+        let category = display(&meta.category);
+        #[cfg(feature = "valuable")]
+        let valuable_category = meta
+            .valuable_category
+            .map(|as_value| as_value(&meta.category));
+        let file_path = if config::normalize_paths() {
+            Cow::Owned(meta.file.replace('\\', "/"))
+        } else {
+            Cow::Borrowed(meta.file)
+        };
+        let file = display(&file_path);
+        let line = display(&meta.line);
+        let module = display(&meta.module);
+        let error_id = display(&meta.id);
+        let parent_error_id = meta.parent_id.as_ref().map(display);
+        let tags = debug(&meta.tags);
+        let breadcrumbs = debug(&meta.breadcrumbs);
+        let time_remaining_ms = meta.deadline.map(|deadline| {
+            let now = Instant::now();
+            match deadline.checked_duration_since(now) {
+                Some(remaining) => remaining.as_millis() as i64,
+                None => -((now - deadline).as_millis() as i64),
             }
-            Level::INFO => {
-                info!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
+        });
+        let remote_ip = meta
+            .http
+            .as_ref()
+            .and_then(|http| http.remote_ip)
+            .map(display);
+        let build_info = config::build_info();
+        let message = display(error);
+        let templated_message = config::message_template().map(|template| {
+            config::render_template(&template, |placeholder| match placeholder {
+                "category" => Some(meta.category.to_string()),
+                "public" => Some(format!("{:?}", self.public)),
+                "private" => Some(error.to_string()),
+                "error_id" => Some(meta.id.to_string()),
+                _ => None,
+            })
+        });
+
+        let mut fields: Vec<(&str, &dyn Value)> = Vec::with_capacity(7 + keys.len());
+        if emit_chain {
+            match &combined_chains {
+                Some(chains) => fields.push((names.errors, chains)),
+                None => fields.push((names.errors, &errors_field)),
             }
-            Level::DEBUG if has_fields => {
-                debug!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    additional_context = ?meta.fields,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
+        }
+        if let Some(categories) = &combined_categories {
+            fields.push(("combined_categories", categories));
+        }
+        let public_error_log_mode = config::public_error_log_mode();
+        if public_error_log_mode == PublicErrorLogMode::Full {
+            fields.push((names.public_error, &public_error));
+        }
+        if public_error_log_mode != PublicErrorLogMode::Omit {
+            fields.push(("public_error_variant", &public_error_variant));
+        }
+        #[cfg(feature = "valuable")]
+        match &valuable_category {
+            Some(category) => fields.push((names.category, category)),
+            None => fields.push((names.category, &category)),
+        }
+        #[cfg(not(feature = "valuable"))]
+        fields.push((names.category, &category));
+        fields.push(("error_id", &error_id));
+        if let Some(parent_error_id) = &parent_error_id {
+            fields.push(("parent_error_id", parent_error_id));
+        }
+        let recorded: Vec<_> = keys
+            .iter()
+            .map(|key| meta.fields[*key].recorded())
+            .collect();
+        for (name, value) in context_names.iter().zip(&recorded) {
+            fields.push((name, value.as_value()));
+        }
+        if let Some(truncated) = &fields_truncated {
+            fields.push(("fields_truncated", truncated));
+        }
+        if !meta.tags.is_empty() {
+            fields.push(("tags", &tags));
+        }
+        if !meta.breadcrumbs.is_empty() {
+            fields.push(("context_breadcrumbs", &breadcrumbs));
+        }
+        if let Some((resource_type, resource_id)) = &meta.resource {
+            fields.push(("resource.type", resource_type));
+            fields.push(("resource.id", resource_id));
+        }
+        if let Some(http) = &meta.http {
+            fields.push(("http.method", &http.method));
+            fields.push(("http.path", &http.path));
+            if let Some(query) = &http.query {
+                fields.push(("http.query", query));
             }
-            Level::DEBUG => {
-                debug!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
+            if let Some(remote_ip) = &remote_ip {
+                fields.push(("http.remote_ip", remote_ip));
             }
-            Level::TRACE if has_fields => {
-                trace!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    additional_context = ?meta.fields,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
+            if let Some(user_agent) = &http.user_agent {
+                fields.push(("http.user_agent", user_agent));
             }
-            Level::TRACE => {
-                trace!(
-                    errors = ?errors,
-                    public_error = ?self.public,
-                    category = %meta.category,
-                    file = %meta.file, line = %meta.line as i64,
-                    module = %meta.module,
-                    "{}", error
-                );
+        }
+        if let Some((commit, built_at)) = &build_info {
+            fields.push(("build.commit", commit));
+            fields.push(("build.timestamp", built_at));
+        }
+        if let Some(remaining) = &time_remaining_ms {
+            fields.push(("time_remaining_ms", remaining));
+        }
+        if self.is_deadline_exceeded() {
+            fields.push(("deadline_exceeded", &true));
+        }
+        let os_error = self.os_error().map(i64::from);
+        if let Some(os_error) = &os_error {
+            fields.push(("os_error", os_error));
+        }
+        if let Some(remediation) = &meta.remediation {
+            fields.push(("remediation_url", remediation));
+        }
+        let elapsed_ms = meta.elapsed.map(|elapsed| elapsed.as_millis() as u64);
+        if let Some(elapsed_ms) = &elapsed_ms {
+            fields.push(("elapsed_ms", elapsed_ms));
+        }
+        fields.push(("retryable", &meta.retryable));
+        let retry_after_ms = meta.retry_after.map(|after| after.as_millis() as u64);
+        if let Some(retry_after_ms) = &retry_after_ms {
+            fields.push(("retry_after_ms", retry_after_ms));
+        }
+        #[cfg(feature = "backtrace")]
+        let backtrace = meta.backtrace.as_deref().map(debug);
+        #[cfg(feature = "backtrace")]
+        if let Some(backtrace) = &backtrace {
+            fields.push(("backtrace", backtrace));
+        }
+        #[cfg(feature = "tokio")]
+        let task_id = meta.task_id.map(display);
+        #[cfg(feature = "tokio")]
+        if let Some(task_id) = &task_id {
+            fields.push(("task_id", task_id));
+        }
+        fields.push((names.file, &file));
+        fields.push((names.line, &line));
+        // Only the macros know the module, not the functions which track their caller
+        if !meta.module.is_empty() {
+            fields.push((names.module, &module));
+        }
+        match &templated_message {
+            Some(templated) => fields.push(("message", templated)),
+            None => fields.push(("message", &message)),
+        }
+
+        let location = Location {
+            target: meta.target.unwrap_or(LOG_TARGET),
+            file: &file_path,
+            line: meta.line,
+            module: meta.module,
+        };
+        let emit = || {
+            emit::emit_error(level, &location, &fields);
+            #[cfg(feature = "otel")]
+            if otel::record_span_status() {
+                self.set_otel_span_status();
             }
+        };
+        #[cfg(feature = "tracing")]
+        match &meta.span {
+            Some(span) => span.in_scope(emit),
+            None => emit(),
         }
-        self.meta.has_logged = true;
+        #[cfg(not(feature = "tracing"))]
+        emit();
+        sink::dispatch(|| LogRecord {
+            level,
+            error_id: meta.id,
+            category: meta.category.to_string(),
+            public_error: format!("{:?}", self.public),
+            errors,
+            fields: keys
+                .iter()
+                .map(|key| (key.to_string(), meta.fields[*key].clone()))
+                .collect(),
+            file: file_path.to_string(),
+            line: meta.line,
+            module: meta.module.to_string(),
+            message: templated_message
+                .clone()
+                .unwrap_or_else(|| error.to_string()),
+        });
+        self.meta.has_logged.set(true);
     }
-}
 
-impl<Pub, Cat> fmt::Debug for DetailedError<Pub, Cat>
-where
-    Cat: Display,
-    Pub: ToResponse + Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.meta.category)
+    /// The unique id generated for this error, emitted as `error_id`
+    pub fn error_id(&self) -> ErrorId {
+        self.meta.id
     }
-}
 
-impl<Pub, Cat> fmt::Display for DetailedError<Pub, Cat>
-where
-    Cat: Display,
-    Pub: ToResponse + Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.private)
+    /// Create a copy of this error for the next attempt of an operation which is being retried,
+    /// the copy gets its own [`ErrorId`] and is logged as a separate event. It's still the same
+    /// error though, so it isn't counted again by the `metrics` counter or the aggregate counts.
+    ///
+    /// As the private error can't be cloned, the copy holds a snapshot of its chain.
+    pub fn clone_for_retry(&self) -> Self
+    where
+        Pub: Clone,
+        Cat: Clone,
+    {
+        let mut meta = self.meta.clone();
+        meta.id = ErrorId::new();
+        meta.has_logged.set(false);
+        let mut err = DetailedError {
+            private: InnerError::new(Snapshot::new(self.private.chain())),
+            public: self.public.clone(),
+            meta,
+        };
+        err.log();
+        err
     }
-}
 
-impl<Pub, Cat> StdError for DetailedError<Pub, Cat>
-where
-    Cat: Display,
-    Pub: ToResponse + Debug,
-{
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        self.private.source()
+    /// Add free-form tags to the error (eg. `transient` or `alertable`), these are emitted as a
+    /// single `tags` field
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_tags<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.meta
+            .tags
+            .extend(tags.into_iter().map(|t| t.as_ref().to_string()));
+        self
     }
-}
 
-impl<P, Cat> Deref for DetailedError<P, Cat>
-where
-    Cat: Display,
-    P: ToResponse + Debug,
-{
-    type Target = InnerError;
+    pub fn tags(&self) -> &[String] {
+        &self.meta.tags
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.private
+    /// Attach the resource the error concerns (eg. `User` `42`), emitted as `resource.type` and
+    /// `resource.id` so that they're named consistently across errors
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_resource(mut self, resource_type: &str, resource_id: impl Display) -> Self {
+        self.meta.resource = Some((resource_type.to_string(), resource_id.to_string()));
+        self
     }
-}
 
-/// Create a new error and emit an event with [`tracing::Level::ERROR`]
-///
-/// This is shorthand for `detailed_error!(Level::ERROR, ...)`
-#[macro_export]
-macro_rules! e {
+    /// The type and id of the resource the error concerns
+    pub fn resource(&self) -> Option<(&str, &str)> {
+        self.meta
+            .resource
+            .as_ref()
+            .map(|(resource_type, resource_id)| (resource_type.as_str(), resource_id.as_str()))
+    }
+
+    /// Attach the request the error occurred while handling, emitted as `http.method`,
+    /// `http.path`, `http.query`, `http.remote_ip` and `http.user_agent`
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_http_context(mut self, http: HttpContext) -> Self {
+        self.meta.http = Some(http);
+        self
+    }
+
+    pub fn http_context(&self) -> Option<&HttpContext> {
+        self.meta.http.as_ref()
+    }
+
+    /// Record that a field of the request failed validation, these are included in the responses
+    /// which support them (eg. [`to_grpc_status_with_details`](Self::to_grpc_status_with_details))
+    pub fn with_field_violation(mut self, field: &str, description: impl Display) -> Self {
+        self.meta
+            .violations
+            .push((field.to_string(), description.to_string()));
+        self
+    }
+
+    /// The fields which failed validation and why
+    pub fn field_violations(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.meta
+            .violations
+            .iter()
+            .map(|(field, description)| (field.as_str(), description.as_str()))
+    }
+
+    /// Link to a runbook or docs page for the operators dealing with the error, this is emitted as
+    /// `remediation_url`
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_remediation(mut self, url: &str) -> Self {
+        self.meta.remediation = Some(url.to_string());
+        self
+    }
+
+    pub fn remediation(&self) -> Option<&str> {
+        self.meta.remediation.as_deref()
+    }
+
+    /// Link the error to the one it was created in response to, eg. when a lower layer's error is
+    /// caught and re-raised with a different category, the parent's id is emitted as
+    /// `parent_error_id` so the error can be followed across the layers
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged, see
+    /// [`DetailedErrorBuilder::parent`] to set it when the error is built.
+    pub fn with_parent<P, C>(mut self, parent: &DetailedError<P, C>) -> Self
+    where
+        C: Display,
+        P: ToResponse + Debug,
+    {
+        self.meta.parent_id = Some(parent.meta.id);
+        self
+    }
+
+    pub fn parent_error_id(&self) -> Option<ErrorId> {
+        self.meta.parent_id
+    }
+
+    /// Mark whether the operation which failed can be retried, so that a generic retry layer can
+    /// decide without knowing the concrete error, this is emitted as `retryable`
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_retryable(mut self, retryable: bool) -> Self {
+        self.meta.retryable = retryable;
+        self
+    }
+
+    /// Mark the operation which failed as retryable after waiting for `after`, this is emitted as
+    /// `retry_after_ms`
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_retry_after(mut self, after: Duration) -> Self {
+        self.meta.retryable = true;
+        self.meta.retry_after = Some(after);
+        self
+    }
+
+    /// Whether the operation which failed can be retried, errors aren't unless they've been marked
+    /// as such
+    pub fn is_retryable(&self) -> bool {
+        self.meta.retryable
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.meta.retry_after
+    }
+
+    /// Emit the event under `target` rather than this crate's, eg. the caller's `module_path!()`
+    /// so that it's filtered along with the rest of the caller's events
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_target(mut self, target: &'static str) -> Self {
+        self.meta.target = Some(target);
+        self
+    }
+
+    /// Where the error was created, this is only captured when it's enabled through the
+    /// `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables and is emitted as
+    /// `backtrace`
+    #[cfg(feature = "backtrace")]
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.meta.backtrace.as_deref()
+    }
+
+    /// Associate the error with a span other than the current one, eg. when it's created in a
+    /// callback which runs outside of the span it belongs to, the event is emitted within it
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    #[cfg(feature = "tracing")]
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.meta.span = Some(span);
+        self
+    }
+
+    #[cfg(feature = "tracing")]
+    pub fn span(&self) -> Option<&Span> {
+        self.meta.span.as_ref()
+    }
+
+    /// Attach the deadline of the operation which failed, the time which was remaining when the
+    /// error is logged is emitted as `time_remaining_ms` (negative if it's been exceeded)
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.meta.deadline = Some(deadline);
+        self
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.meta.deadline
+    }
+
+    /// The tokio task which the error was created in, this is emitted as `task_id`, and is `None`
+    /// when it was created outside of a task
+    #[cfg(feature = "tokio")]
+    pub fn task_id(&self) -> Option<tokio::task::Id> {
+        self.meta.task_id
+    }
+
+    /// Record how long the operation which failed took, this is emitted as `elapsed_ms`, see
+    /// [`time_operation`] for measuring it automatically
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.meta.elapsed = Some(elapsed);
+        self
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.meta.elapsed
+    }
+
+    /// How long there is left until the deadline, this is zero once it's been exceeded
+    pub fn time_remaining(&self) -> Option<Duration> {
+        self.meta
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether the error was caused by running out of time, either because the
+    /// [deadline](Self::with_deadline) has passed or because one of the errors in the chain is a
+    /// timeout (an [`io::Error`](std::io::Error) of kind `TimedOut`, or with the `tokio` feature a
+    /// `tokio::time::error::Elapsed`), this is emitted as `deadline_exceeded = true`
+    pub fn is_deadline_exceeded(&self) -> bool {
+        let past_deadline = self
+            .meta
+            .deadline
+            .is_some_and(|deadline| deadline <= Instant::now());
+        past_deadline || self.private.chain().any(is_timeout)
+    }
+
+    /// The private error followed by each of its sources, including any contexts which have been
+    /// added, outermost first
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        self.private.chain()
+    }
+
+    /// The last error in the [chain](Self::chain), the underlying cause of the error
+    pub fn root_cause(&self) -> &(dyn StdError + 'static) {
+        self.private.root_cause()
+    }
+
+    /// Borrow the concrete type of the private error, or of any of the contexts which have been
+    /// added to it, eg. to check the [`ErrorKind`](std::io::ErrorKind) of an [`io::Error`](std::io::Error)
+    ///
+    /// The copies made by [`clone_for_retry`](Self::clone_for_retry) only hold a snapshot of the
+    /// chain, so this always returns `None` for them.
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        self.private.downcast_ref::<E>().or_else(|| {
+            self.private
+                .downcast_ref::<lazy::SharedError<E>>()
+                .map(|shared| &*shared.0)
+        })
+    }
+
+    /// Take back the concrete type of the private error, or of any of the contexts which have been
+    /// added to it, returning the error as it was when it's a different type
+    ///
+    /// Everything else about the error is discarded, so this should only be used once it's been
+    /// logged.
+    pub fn downcast<E>(self) -> Result<E, Self>
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        match self.private.downcast::<E>() {
+            Ok(private) => Ok(private),
+            Err(private) => Err(DetailedError {
+                private,
+                public: self.public,
+                meta: self.meta,
+            }),
+        }
+    }
+
+    /// The backtrace captured by `anyhow` when the private error was created, if there is one
+    #[cfg(all(any(feature = "otel", feature = "opentelemetry"), feature = "anyhow"))]
+    pub(crate) fn stacktrace(&self) -> Option<String> {
+        let backtrace = self.private.backtrace();
+        match backtrace.status() {
+            std::backtrace::BacktraceStatus::Captured => Some(backtrace.to_string()),
+            _ => None,
+        }
+    }
+
+    #[cfg(all(
+        any(feature = "otel", feature = "opentelemetry"),
+        not(feature = "anyhow")
+    ))]
+    pub(crate) fn stacktrace(&self) -> Option<String> {
+        None
+    }
+
+    /// The raw OS error code (eg. `errno`) when one of the errors in the chain is an
+    /// [`io::Error`](std::io::Error) which has one, this is emitted as `os_error`
+    ///
+    /// When there's more than one, the one closest to the root cause is used.
+    pub fn os_error(&self) -> Option<i32> {
+        self.private
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+            .filter_map(std::io::Error::raw_os_error)
+            .last()
+    }
+
+    /// Record the category through its [`Valuable`](valuable::Valuable) impl rather than its
+    /// [`Display`] impl, so that the data held by the category is kept structured
+    ///
+    /// ```ignore
+    /// #[derive(Valuable)]
+    /// enum Category {
+    ///     NotFound { resource: String },
+    /// }
+    /// ```
+    ///
+    /// This requires building with `RUSTFLAGS="--cfg tracing_unstable"` and only has an effect on
+    /// the emitted event if it's used before the error is logged.
+    #[cfg(feature = "valuable")]
+    pub fn with_valuable_category(mut self) -> Self
+    where
+        Cat: valuable::Valuable,
+    {
+        self.meta.valuable_category = Some(|category| category.as_value());
+        self
+    }
+
+    /// Add a field to be emitted alongside the error, replacing any existing field with that key
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged, see
+    /// [`relog`](Self::relog) for emitting the error again with the fields added afterwards.
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<FieldValue>) -> Self {
+        self.insert_field(key, value);
+        self
+    }
+
+    /// See [`with_field`](Self::with_field)
+    pub fn insert_field(&mut self, key: impl Into<String>, value: impl Into<FieldValue>) {
+        self.meta
+            .fields
+            .insert(Cow::Owned(key.into()), value.into());
+    }
+
+    /// Emit the error again even though it's already been logged, eg. once it's been enriched with
+    /// fields which weren't available where it was created
+    ///
+    /// The event has the same [`ErrorId`] as the first one, so the two can be tied together.
+    pub fn relog(&mut self) {
+        self.meta.has_logged.set(false);
+        self.log();
+    }
+
+    /// Remove all of the additional fields which don't satisfy the predicate
+    ///
+    /// This only has an effect on the emitted event if it's used before the error is logged.
+    pub fn retain_fields<F>(&mut self, pred: F)
+    where
+        F: Fn(&str, &FieldValue) -> bool,
+    {
+        self.meta.fields.retain(|key, value| pred(key, value));
+    }
+
+    /// Emit an event which only contains the public-safe information about this error, the private
+    /// error and its chain are never included.
+    ///
+    /// The event has the category, the [status code](ToResponse::status_code), the
+    /// [error code](ToResponse::error_code) if there is one and the error id, with the
+    /// [name of the public error](ToResponse::variant_name) as its message. The configured
+    /// [field names](crate::set_field_names) and [key case](crate::set_key_case) apply the same as
+    /// they do to [`DetailedError::log`].
+    ///
+    /// The event is emitted under the [`PUBLIC_TARGET`] target (or `<target>::public` when the
+    /// error has a [target](DetailedError::with_target)) so that it can be routed to a separate
+    /// sink (eg. an access log) from the full event emitted by [`DetailedError::log`]. It has no
+    /// effect on whether the error is considered to have been logged.
+    pub fn emit_public_only(&self) {
+        #[cfg(feature = "tracing")]
+        {
+            let meta = &self.meta;
+            let names = config::field_names();
+            let category = display(&meta.category);
+            let status = u64::from(self.public.status_code());
+            let code = self.public.error_code();
+            let code = code.as_ref().map(ErrorCode::as_str);
+            let error_id = display(&meta.id);
+            let message = public_variant(&self.public);
+
+            let mut fields: Vec<(&str, &dyn Value)> = Vec::with_capacity(5);
+            fields.push((names.category, &category));
+            fields.push(("status", &status));
+            if let Some(code) = &code {
+                fields.push(("code", code));
+            }
+            fields.push(("error_id", &error_id));
+            fields.push(("message", &message));
+
+            let target = match meta.target {
+                Some(target) => {
+                    emit::intern(&format!("{}::public", target)).unwrap_or(PUBLIC_TARGET)
+                }
+                None => PUBLIC_TARGET,
+            };
+            let location = Location {
+                target,
+                file: meta.file,
+                line: meta.line,
+                module: meta.module,
+            };
+            emit::emit(meta.level, &location, &fields);
+        }
+    }
+}
+
+/// The name emitted as `public_error_variant`, see [`ToResponse::variant_name`]
+#[cfg(feature = "std")]
+fn public_variant<Pub: ToResponse + Debug>(public: &Pub) -> String {
+    match public.variant_name() {
+        Some(name) => name.to_string(),
+        None => {
+            let debug = format!("{:?}", public);
+            let end = debug.find([' ', '(', '{']).unwrap_or(debug.len());
+            debug[..end].to_string()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn is_timeout(cause: &(dyn StdError + 'static)) -> bool {
+    if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+        return io.kind() == std::io::ErrorKind::TimedOut;
+    }
+    #[cfg(feature = "tokio")]
+    if cause.is::<tokio::time::error::Elapsed>() {
+        return true;
+    }
+    false
+}
+
+#[cfg(feature = "std")]
+impl<Pub, Cat> fmt::Debug for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.meta.category)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Pub, Cat> fmt::Display for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.private)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Pub, Cat> StdError for DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.private.source()
+    }
+}
+
+#[cfg(feature = "std")]
+/// Extensions for a [`Result`] which already holds a [`DetailedError`]
+pub trait DetailedResultExt<T, Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// See [`DetailedError::convert_public`]
+    fn convert_public<P2>(self) -> Result<T, DetailedError<P2, Cat>>
+    where
+        P2: ToResponse + Debug + From<Pub>;
+
+    /// Discard the error for an operation which is allowed to fail, making sure it's been logged
+    /// (it's only logged again if it wasn't already)
+    ///
+    /// ```ignore
+    /// let cached = maybe_load().ok_logged();
+    /// ```
+    fn ok_logged(self) -> Option<T>;
+
+    /// Recover from the error when one of the errors in its chain is an `E` and `f` returns a
+    /// value for it, eg. to fall back to a default for a transient failure. Otherwise the error is
+    /// returned as is.
+    fn recover_if<E, F>(self, f: F) -> Self
+    where
+        E: StdError + 'static,
+        F: FnOnce(&E) -> Option<T>;
+}
+
+#[cfg(feature = "std")]
+impl<T, Pub, Cat> DetailedResultExt<T, Pub, Cat> for Result<T, DetailedError<Pub, Cat>>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn convert_public<P2>(self) -> Result<T, DetailedError<P2, Cat>>
+    where
+        P2: ToResponse + Debug + From<Pub>,
+    {
+        self.map_err(DetailedError::convert_public)
+    }
+
+    fn ok_logged(self) -> Option<T> {
+        match self {
+            Ok(t) => Some(t),
+            Err(mut e) => {
+                e.log();
+                None
+            }
+        }
+    }
+
+    fn recover_if<E, F>(self, f: F) -> Self
+    where
+        E: StdError + 'static,
+        F: FnOnce(&E) -> Option<T>,
+    {
+        let err = match self {
+            Ok(t) => return Ok(t),
+            Err(err) => err,
+        };
+        let recovered = err
+            .private
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<E>())
+            .and_then(f);
+        match recovered {
+            Some(t) => Ok(t),
+            None => Err(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Wrap the private error of a [`Result`] which already holds a [`DetailedError`] with some
+/// additional context, like `anyhow::Context`
+///
+/// The error will usually have been logged when it was created, and adding context doesn't log it
+/// again, as every layer that adds context would emit its own copy of the event otherwise. The
+/// context is part of any later event (eg. through [`relog`](DetailedError::relog)) and of the
+/// private error chain.
+///
+/// As [`DetailedError`] implements [`Error`](StdError) this clashes with `anyhow::Context` when
+/// both are in scope.
+pub trait DetailedContext<T, Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// See [`DetailedError::context`]
+    fn context<C>(self, ctx: C) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        C: Display + Send + Sync + 'static;
+
+    /// The same as [`context`](Self::context), with the context only built for an error
+    fn with_context<C, F>(self, f: F) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C;
+}
+
+#[cfg(feature = "std")]
+impl<T, Pub, Cat> DetailedContext<T, Pub, Cat> for Result<T, DetailedError<Pub, Cat>>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn context<C>(self, ctx: C) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        C: Display + Send + Sync + 'static,
+    {
+        self.map_err(|err| err.context(ctx))
+    }
+
+    fn with_context<C, F>(self, f: F) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|err| err.context(f()))
+    }
+}
+
+#[cfg(feature = "std")]
+/// Extensions for turning any [`Result`] into one which holds a [`DetailedError`], as a method
+/// chaining alternative to the macros
+///
+/// ```ignore
+/// let file = File::open(path).or_detailed_context(PublicError::NotFound, Category::Io, "opening config")?;
+/// ```
+///
+/// Like [`DetailedError::msg`] the error is logged straight away at the
+/// [default level](set_default_level), and its location is where the method is called.
+pub trait ResultExt<T, E> {
+    fn or_detailed<Pub, Cat>(
+        self,
+        public: Pub,
+        category: Cat,
+    ) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        Cat: Display,
+        Pub: ToResponse + Debug;
+
+    fn or_detailed_context<Pub, Cat, C>(
+        self,
+        public: Pub,
+        category: Cat,
+        ctx: C,
+    ) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        Cat: Display,
+        Pub: ToResponse + Debug,
+        C: Display + Send + Sync + 'static;
+}
+
+#[cfg(feature = "std")]
+impl<T, E> ResultExt<T, E> for Result<T, E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    #[track_caller]
+    fn or_detailed<Pub, Cat>(self, public: Pub, category: Cat) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        Cat: Display,
+        Pub: ToResponse + Debug,
+    {
+        let location = std::panic::Location::caller();
+        self.map_err(|private| {
+            DetailedError::new(
+                private,
+                public,
+                None::<&str>,
+                category,
+                config::default_level(),
+                location.file(),
+                location.line(),
+                "",
+            )
+        })
+    }
+
+    #[track_caller]
+    fn or_detailed_context<Pub, Cat, C>(
+        self,
+        public: Pub,
+        category: Cat,
+        ctx: C,
+    ) -> Result<T, DetailedError<Pub, Cat>>
+    where
+        Cat: Display,
+        Pub: ToResponse + Debug,
+        C: Display + Send + Sync + 'static,
+    {
+        let location = std::panic::Location::caller();
+        self.map_err(|private| {
+            DetailedError::new(
+                private,
+                public,
+                Some(ctx),
+                category,
+                config::default_level(),
+                location.file(),
+                location.line(),
+                "",
+            )
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P, Cat> Deref for DetailedError<P, Cat>
+where
+    Cat: Display,
+    P: ToResponse + Debug,
+{
+    type Target = InnerError;
+
+    fn deref(&self) -> &Self::Target {
+        &self.private
+    }
+}
+
+/// Create a new error and emit an event with [`Level::ERROR`]
+///
+/// This is shorthand for `detailed_error!(Level::ERROR, ...)`
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! e {
+    ($private:ident, $public:expr, $category:expr) => {
+        $crate::detailed_error!($crate::Level::ERROR, $private, $public, $category)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr) => {
+        $crate::detailed_error!($crate::Level::ERROR, $private, $public, $category, $ctx)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr, $($fields:tt)+) => {
+        $crate::detailed_error!($crate::Level::ERROR, $private, $public, $category, $ctx, $($fields)+)
+    };
+}
+
+/// Create a new error and emit an event with [`Level::INFO`]
+///
+/// This is shorthand for `detailed_error!(Level::INFO, ...)`
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! i {
+    ($private:ident, $public:expr, $category:expr) => {
+        $crate::detailed_error!($crate::Level::INFO, $private, $public, $category)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr) => {
+        $crate::detailed_error!($crate::Level::INFO, $private, $public, $category, $ctx)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr, $($fields:tt)+) => {
+        $crate::detailed_error!($crate::Level::INFO, $private, $public, $category, $ctx, $($fields)+)
+    };
+}
+
+/// Create a new error and emit an event with [`Level::DEBUG`]
+///
+/// This is shorthand for `detailed_error!(Level::DEBUG, ...)`
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! d {
     ($private:ident, $public:expr, $category:expr) => {
-        $crate::detailed_error!(tracing::Level::ERROR, $private, $public, $category)
+        $crate::detailed_error!($crate::Level::DEBUG, $private, $public, $category)
     };
     ($private:ident, $public:expr, $category:expr, $ctx:expr) => {
-        $crate::detailed_error!(tracing::Level::ERROR, $private, $public, $category, $ctx)
+        $crate::detailed_error!($crate::Level::DEBUG, $private, $public, $category, $ctx)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr, $($fields:tt)+) => {
+        $crate::detailed_error!($crate::Level::DEBUG, $private, $public, $category, $ctx, $($fields)+)
     };
 }
 
-/// Create a new error and emit an event with [`tracing::Level::WARN`]
+/// Create a new error and emit an event with [`Level::TRACE`]
+///
+/// This is shorthand for `detailed_error!(Level::TRACE, ...)`
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! t {
+    ($private:ident, $public:expr, $category:expr) => {
+        $crate::detailed_error!($crate::Level::TRACE, $private, $public, $category)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr) => {
+        $crate::detailed_error!($crate::Level::TRACE, $private, $public, $category, $ctx)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr, $($fields:tt)+) => {
+        $crate::detailed_error!($crate::Level::TRACE, $private, $public, $category, $ctx, $($fields)+)
+    };
+}
+
+/// Create a new error and emit an event with [`Level::WARN`]
 ///
 /// This is shorthand for `detailed_error!(Level::WARN, ...)`
+///
+/// **Breaking change:** the arguments are in the same order as [`e!`], ie.
+/// `w!(private, public, category, ctx)`. Previously the context came before the category, so any
+/// existing calls with a context need to swap those two arguments.
+#[cfg(feature = "std")]
 #[macro_export]
 macro_rules! w {
     ($private:ident, $public:expr, $category:expr) => {
-        $crate::detailed_error!(tracing::Level::WARN, $private, $public, $category)
+        $crate::detailed_error!($crate::Level::WARN, $private, $public, $category)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr) => {
+        $crate::detailed_error!($crate::Level::WARN, $private, $public, $category, $ctx)
+    };
+    ($private:ident, $public:expr, $category:expr, $ctx:expr, $($fields:tt)+) => {
+        $crate::detailed_error!($crate::Level::WARN, $private, $public, $category, $ctx, $($fields)+)
+    };
+}
+
+/// Create a new error from a message and emit an event with [`Level::ERROR`]
+///
+/// This is the equivalent of `anyhow!`/`eyre!` for when there's no underlying error type
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! e_msg {
+    ($msg:expr, $public:expr, $category:expr) => {
+        $crate::DetailedError::new_msg(
+            $msg,
+            $public,
+            $category,
+            $crate::Level::ERROR,
+            std::file!(),
+            std::line!(),
+            std::module_path!(),
+        )
+    };
+}
+
+/// Create a new error with the provided error level without emitting an event, see
+/// [`DetailedError::new_lazy`]
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! detailed_error_lazy {
+    ($lvl:path, $private:ident, $public:expr, $category:expr) => {
+        $crate::DetailedError::new_lazy(
+            $private,
+            $public,
+            None::<&str>,
+            $category,
+            $lvl,
+            std::file!(),
+            std::line!(),
+            std::module_path!(),
+            std::collections::HashMap::with_capacity(0),
+        )
     };
-    ($private:ident, $public:expr, $ctx:expr, $category:expr) => {
-        $crate::detailed_error!(tracing::Level::WARN, $private, $public, $category, $ctx)
+    ($lvl:path, $private:ident, $public:expr, $category:expr, $ctx:expr) => {
+        $crate::DetailedError::new_lazy(
+            $private,
+            $public,
+            Some($ctx),
+            $category,
+            $lvl,
+            std::file!(),
+            std::line!(),
+            std::module_path!(),
+            std::collections::HashMap::with_capacity(0),
+        )
     };
+    // When every key is a literal the string literals are borrowed, otherwise each key is
+    // allocated through its `Display` impl
+    ($lvl:path, $private:ident, $public:expr, $category:expr, $ctx:expr, $($k:literal => $v:expr),* $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{DisplayKey, StaticKey, ViaDisplay, ViaFrom};
+        let map: std::collections::HashMap<std::borrow::Cow<'static, str>, $crate::FieldValue> = std::convert::From::from([$(((&$crate::__private::Wrap(&$k)).field_key(), (&$crate::__private::Wrap(&$v)).field_value()),)*]);
+        $crate::DetailedError::new_lazy(
+            $private,
+            $public,
+            Some($ctx),
+            $category,
+            $lvl,
+            std::file!(),
+            std::line!(),
+            std::module_path!(),
+            map,
+        )
+    }};
+    ($lvl:path, $private:ident, $public:expr, $category:expr, $ctx:expr, $($k:expr => $v:expr),* $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{ViaDisplay, ViaFrom};
+        let map: std::collections::HashMap<std::borrow::Cow<'static, str>, $crate::FieldValue> = std::convert::From::from([$((std::borrow::Cow::Owned($k.to_string()), (&$crate::__private::Wrap(&$v)).field_value()),)*]);
+        $crate::DetailedError::new_lazy(
+            $private,
+            $public,
+            Some($ctx),
+            $category,
+            $lvl,
+            std::file!(),
+            std::line!(),
+            std::module_path!(),
+            map,
+        )
+    }};
 }
 
 /// Create a new error and emit an event with with the provided error level
+#[cfg(feature = "std")]
 #[macro_export]
 macro_rules! detailed_error {
     ($lvl:path, $private:ident, $public:expr, $category:expr) => {
         $crate::DetailedError::new(
             $private,
             $public,
-            None,
+            None::<&str>,
             $category,
             $lvl,
-            std::file!().to_string(),
+            std::file!(),
             std::line!(),
-            std::module_path!().to_string(),
+            std::module_path!(),
         )
     };
     ($lvl:path, $private:ident, $public:expr, $category:expr, $ctx:expr) => {
@@ -446,23 +2182,1126 @@ macro_rules! detailed_error {
             Some($ctx),
             $category,
             $lvl,
-            std::file!().to_string(),
+            std::file!(),
             std::line!(),
-            std::module_path!().to_string(),
+            std::module_path!(),
         )
     };
+    // When every key is a literal the string literals are borrowed, otherwise each key is
+    // allocated through its `Display` impl
+    ($lvl:path, $private:ident, $public:expr, $category:expr, $ctx:expr, $($k:literal => $v:expr),* $(,)?) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{DisplayKey, StaticKey, ViaDisplay, ViaFrom};
+        let map: std::collections::HashMap<std::borrow::Cow<'static, str>, $crate::FieldValue> = std::convert::From::from([$(((&$crate::__private::Wrap(&$k)).field_key(), (&$crate::__private::Wrap(&$v)).field_value()),)*]);
+        $crate::DetailedError::new_with_tracing(
+            $private,
+            $public,
+            Some($ctx),
+            $category,
+            $lvl,
+            std::file!(),
+            std::line!(),
+            std::module_path!(),
+            map,
+        )
+    }};
     ($lvl:path, $private:ident, $public:expr, $category:expr, $ctx:expr, $($k:expr => $v:expr),* $(,)?) => {{
-        let mut map: std::collections::HashMap<String, String> = std::convert::From::from([$(($k.to_string(), $v.to_string()),)*]);
+        #[allow(unused_imports)]
+        use $crate::__private::{ViaDisplay, ViaFrom};
+        let map: std::collections::HashMap<std::borrow::Cow<'static, str>, $crate::FieldValue> = std::convert::From::from([$((std::borrow::Cow::Owned($k.to_string()), (&$crate::__private::Wrap(&$v)).field_value()),)*]);
         $crate::DetailedError::new_with_tracing(
             $private,
             $public,
             Some($ctx),
             $category,
             $lvl,
-            std::file!().to_string(),
+            std::file!(),
             std::line!(),
-            std::module_path!().to_string(),
+            std::module_path!(),
             map,
         )
     }};
 }
+
+/// Create a span with the fields [`DetailedError::log_to_span`] records declared (`error`,
+/// `category` and `error_id`), along with any of the error's additional fields which should be
+/// recorded
+///
+/// ```ignore
+/// let span = error_span!(Level::INFO, "handle_request", user_id, http.method);
+/// ```
+#[cfg(all(feature = "std", feature = "tracing"))]
+#[macro_export]
+macro_rules! error_span {
+    ($lvl:expr, $name:expr $(, $($field:ident).+)* $(,)?) => {
+        $crate::__private::tracing::span!(
+            $lvl,
+            $name,
+            error = $crate::__private::tracing::field::Empty,
+            category = $crate::__private::tracing::field::Empty,
+            error_id = $crate::__private::tracing::field::Empty,
+            $($($field).+ = $crate::__private::tracing::field::Empty,)*
+        )
+    };
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[cfg(feature = "tracing")]
+    use crate::test_support::capture;
+    use crate::test_support::{io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    use std::borrow::Cow;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn literal_field_keys_are_borrowed() {
+        let io = io_error();
+        let err: Error = crate::w!(io, PublicError::NotFound, "Users", "loading", "user_id" => 42, "attempt" => 1);
+        assert!(err
+            .fields()
+            .keys()
+            .all(|key| matches!(key, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn dynamic_field_keys_are_owned() {
+        let key = String::from("user_id");
+        let borrowed: &str = &key;
+        let io = io_error();
+        let err: Error =
+            crate::w!(io, PublicError::NotFound, "Users", "loading", borrowed => 42, 7 => "seven");
+        assert!(matches!(
+            err.fields().get_key_value("user_id"),
+            Some((Cow::Owned(_), _))
+        ));
+        assert!(err.fields().contains_key("7"));
+
+        let err = err.with_field(borrowed, 43);
+        assert_eq!(err.fields()["user_id"], crate::FieldValue::I64(43));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn only_logged_errors_are_marked_as_errors() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let _logged: Error = crate::w!(io, PublicError::NotFound, "Users");
+            let _opened: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users");
+        });
+        assert_eq!(events.len(), 2);
+        assert!(events[0].error);
+        assert_eq!(events[0].level, crate::Level::WARN);
+        assert_eq!(events[0].target, "api_error");
+        assert_eq!(events[0].module.as_deref(), Some(module_path!()));
+        assert_eq!(events[0].field("category"), Some("Users"));
+        assert!(!events[1].error);
+    }
+
+    #[cfg(feature = "tracing")]
+    fn lazy_error() -> Error {
+        let io = io_error();
+        crate::detailed_error_lazy!(crate::Level::WARN, io, PublicError::NotFound, "Users")
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn lazy_errors_are_only_logged_once_handled() {
+        let _serial = serial();
+        let (_, events) = capture(|| drop(lazy_error()));
+        assert!(events.is_empty());
+
+        let handlers: [fn(Error); 5] = [
+            |err| {
+                err.to_response();
+                err.to_response();
+            },
+            |err| err.to_response_with(|_| ()),
+            |err| drop(err.into_response_parts()),
+            |err| drop(err.into_inner()),
+            |mut err| {
+                err.log();
+                err.log();
+            },
+        ];
+        for handle in handlers {
+            let (_, events) = capture(|| handle(lazy_error()));
+            assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn public_only_events_leave_out_the_private_chain() {
+        let _serial = serial();
+        let io = io_error();
+        let err: Error = DetailedError::open(io, PublicError::NotFound, "Users")
+            .context("loading the user")
+            .with_field("user_id", 42);
+        let (_, events) = capture(|| err.emit_public_only());
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert!(!event.error);
+        assert_eq!(event.target, crate::PUBLIC_TARGET);
+        assert_eq!(event.field("category"), Some("Users"));
+        assert_eq!(event.field("status"), Some("404"));
+        assert_eq!(
+            event.field("error_id"),
+            Some(err.error_id().to_string().as_str())
+        );
+        assert_eq!(event.field("message"), Some("NotFound"));
+        let rendered = format!("{:?}", event.fields);
+        assert!(!rendered.contains("connection reset"));
+        assert!(!rendered.contains("loading the user"));
+        assert!(!rendered.contains("user_id"));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn public_only_events_follow_the_configuration() {
+        let _serial = serial();
+        let io = io_error();
+        let err: Error =
+            DetailedError::open(io, PublicError::NotFound, "Users").with_target("billing");
+        crate::set_field_names(crate::LogFieldNames {
+            category: "kind",
+            ..crate::LogFieldNames::DEFAULT
+        });
+        crate::set_key_case(crate::KeyCase::Camel);
+        let (_, events) = capture(|| err.emit_public_only());
+        crate::set_key_case(crate::KeyCase::Snake);
+        crate::set_field_names(crate::LogFieldNames::DEFAULT);
+
+        assert_eq!(events[0].target, "billing::public");
+        assert_eq!(events[0].field("kind"), Some("Users"));
+        assert_eq!(
+            events[0].field("errorId"),
+            Some(err.error_id().to_string().as_str())
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn unknown_modules_are_left_out() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let _err: Error = DetailedError::msg("no user", PublicError::NotFound, "Users");
+        });
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].module, None);
+        assert_eq!(events[0].field("module"), None);
+        assert!(events[0].field("file").is_some());
+    }
+
+    #[test]
+    fn e_and_w_take_their_arguments_in_the_same_order() {
+        let io = io_error();
+        let e: Error = crate::e!(io, PublicError::NotFound, "Users", "loading the user");
+        let io = io_error();
+        let w: Error = crate::w!(io, PublicError::NotFound, "Users", "loading the user");
+        assert_eq!(e.category(), w.category());
+        assert_eq!(*w.category(), "Users");
+        assert_eq!(e.private.to_string(), w.private.to_string());
+        assert_eq!(w.private.to_string(), "loading the user");
+        assert_eq!(e.level(), crate::Level::ERROR);
+        assert_eq!(w.level(), crate::Level::WARN);
+    }
+
+    fn with_debug_fields(err: Error) -> Error {
+        err.with_field("user_id", 42)
+            .with_field("debug_query", "select 1")
+            .with_field("debug_plan", "seq scan")
+    }
+
+    #[test]
+    fn retain_fields_removes_the_fields_which_fail_the_predicate() {
+        let mut err = with_debug_fields(DetailedError::open(
+            io_error(),
+            PublicError::NotFound,
+            "Users",
+        ));
+        err.retain_fields(|key, _| !key.starts_with("debug_"));
+        assert_eq!(err.fields().len(), 1);
+        assert!(err.fields().contains_key("user_id"));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_field_filter_is_applied_when_logging() {
+        let _serial = serial();
+        let err = with_debug_fields(DetailedError::open(
+            io_error(),
+            PublicError::NotFound,
+            "Users",
+        ));
+        crate::set_field_filter(|key, _| !key.starts_with("debug_"));
+        let (err, events) = capture(|| {
+            let mut err = err;
+            err.log();
+            err
+        });
+        crate::clear_field_filter();
+        assert_eq!(events[0].field("user_id"), Some("42"));
+        assert!(events[0]
+            .fields
+            .keys()
+            .all(|key| !key.starts_with("debug_")));
+        // The filter only applies to the event, the error keeps its fields
+        assert_eq!(err.fields().len(), 3);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tags_are_emitted_as_an_array() {
+        let _serial = serial();
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .with_tags(["transient", "alertable"]);
+        assert_eq!(err.tags(), ["transient", "alertable"]);
+        let (_, events) = capture(|| {
+            let mut err = err;
+            err.log();
+        });
+        assert_eq!(
+            events[0].field("tags"),
+            Some(r#"["transient", "alertable"]"#)
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn log_into_public_logs_once_and_gives_back_the_public_error() {
+        let _serial = serial();
+        let (public, events) = capture(|| {
+            let io = io_error();
+            let err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            err.log_into_public()
+        });
+        assert_eq!(public, PublicError::NotFound);
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users");
+        let (public, events) = capture(|| err.log_into_public());
+        assert_eq!(public, PublicError::NotFound);
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn emits_the_time_remaining_until_the_deadline() {
+        use std::time::{Duration, Instant};
+
+        let _serial = serial();
+        let now = Instant::now();
+        let future: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .with_deadline(now + Duration::from_secs(60));
+        let past: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .with_deadline(now - Duration::from_secs(60));
+        assert!(future.time_remaining().unwrap() > Duration::from_secs(50));
+        assert_eq!(past.time_remaining(), Some(Duration::ZERO));
+
+        let (_, events) = capture(|| {
+            for mut err in [future, past] {
+                err.log();
+            }
+        });
+        let errors: Vec<_> = events.iter().filter(|event| event.error).collect();
+        let remaining: Vec<i64> = errors
+            .iter()
+            .map(|event| event.field("time_remaining_ms").unwrap().parse().unwrap())
+            .collect();
+        assert!((50_000..=60_000).contains(&remaining[0]));
+        assert!((-70_000..=-60_000).contains(&remaining[1]));
+        assert_eq!(errors[0].field("deadline_exceeded"), None);
+        assert_eq!(errors[1].field("deadline_exceeded"), Some("true"));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum DbCategory {
+        Connection,
+    }
+
+    impl std::fmt::Display for DbCategory {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum AppCategory {
+        Database(DbCategory),
+    }
+
+    impl std::fmt::Display for AppCategory {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    #[test]
+    fn map_category_keeps_everything_else() {
+        let err: DetailedError<PublicError, DbCategory> =
+            DetailedError::open(io_error(), PublicError::NotFound, DbCategory::Connection)
+                .context("loading the user")
+                .with_field("user_id", 42);
+        let id = err.error_id();
+        let mapped = err.map_category(AppCategory::Database);
+        assert_eq!(
+            *mapped.category(),
+            AppCategory::Database(DbCategory::Connection)
+        );
+        assert_eq!(mapped.error_id(), id);
+        assert_eq!(mapped.public, PublicError::NotFound);
+        assert_eq!(mapped.private.to_string(), "loading the user");
+        assert_eq!(mapped.fields()["user_id"], crate::FieldValue::I64(42));
+    }
+
+    #[cfg(feature = "valuable")]
+    #[test]
+    fn valuable_categories_are_recorded_structurally() {
+        use valuable::{Fields, NamedField, NamedValues, StructDef, Structable, Valuable};
+
+        static FIELDS: &[NamedField<'static>] = &[NamedField::new("resource")];
+
+        struct NotFound {
+            resource: String,
+        }
+
+        impl std::fmt::Display for NotFound {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "NotFound")
+            }
+        }
+
+        impl Valuable for NotFound {
+            fn as_value(&self) -> valuable::Value<'_> {
+                valuable::Value::Structable(self)
+            }
+
+            fn visit(&self, visit: &mut dyn valuable::Visit) {
+                visit.visit_named_fields(&NamedValues::new(FIELDS, &[self.resource.as_value()]));
+            }
+        }
+
+        impl Structable for NotFound {
+            fn definition(&self) -> StructDef<'_> {
+                StructDef::new_static("NotFound", Fields::Named(FIELDS))
+            }
+        }
+
+        let _serial = serial();
+        let err: DetailedError<PublicError, NotFound> = DetailedError::open(
+            io_error(),
+            PublicError::NotFound,
+            NotFound {
+                resource: "user".to_string(),
+            },
+        )
+        .with_valuable_category();
+        let (_, events) = capture(|| {
+            let mut err = err;
+            err.log();
+        });
+        let event = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(event.kind("category"), Some("value"));
+        assert_eq!(event.field("category.resource"), Some(r#""user""#));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn each_retry_is_logged_with_its_own_id() {
+        let _serial = serial();
+        let io = io_error();
+        let (ids, events) = capture(|| {
+            let err: Error =
+                crate::w!(io, PublicError::NotFound, "Users", "loading", "user_id" => 42);
+            let retries: Vec<Error> = (0..2).map(|_| err.clone_for_retry()).collect();
+            let mut ids = vec![err.error_id()];
+            for retry in &retries {
+                assert_eq!(retry.category(), err.category());
+                assert_eq!(retry.fields(), err.fields());
+                assert_eq!(retry.private.to_string(), "loading");
+                ids.push(retry.error_id());
+            }
+            ids
+        });
+        let logged: Vec<String> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| event.field("error_id").unwrap().to_string())
+            .collect();
+        let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+        assert_eq!(logged, ids);
+        assert_eq!(
+            ids.iter().collect::<std::collections::HashSet<_>>().len(),
+            3
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn contexts_are_emitted_apart_from_the_causes() {
+        let _serial = serial();
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .context("loading the profile")
+            .context("handling the request");
+        let (_, events) = capture(|| {
+            let mut err = err;
+            err.log();
+        });
+        let event = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(
+            event.field("context_breadcrumbs"),
+            Some(r#"["handling the request", "loading the profile"]"#)
+        );
+        assert_eq!(event.field("errors"), Some(r#"["connection reset"]"#));
+        assert_eq!(event.field("message"), Some("handling the request"));
+    }
+
+    #[test]
+    fn msg_creates_an_error_from_a_message() {
+        let err: Error = DetailedError::msg("user 42 is missing", PublicError::NotFound, "Users");
+        assert_eq!(err.private.to_string(), "user 42 is missing");
+        assert_eq!(err.line(), line!() - 2);
+        assert_eq!(err.file(), file!());
+        assert!(err.is_logged());
+
+        let err: Error = crate::e_msg!("user 42 is missing", PublicError::NotFound, "Users");
+        assert_eq!(err.private.to_string(), "user 42 is missing");
+        assert_eq!(err.module(), module_path!());
+    }
+
+    #[test]
+    fn the_default_level_is_used_unless_one_is_given() {
+        use crate::ResultExt;
+
+        let _serial = serial();
+        let err: Error = DetailedError::msg("missing", PublicError::NotFound, "Users");
+        assert_eq!(err.level(), crate::Level::ERROR);
+
+        crate::set_default_level(crate::Level::WARN);
+        let msg: Error = DetailedError::msg("missing", PublicError::NotFound, "Users");
+        let result: Result<(), _> = Err(io_error());
+        let converted: Error = result
+            .or_detailed(PublicError::NotFound, "Users")
+            .unwrap_err();
+        let io = io_error();
+        let explicit: Error = crate::e!(io, PublicError::NotFound, "Users");
+        crate::set_default_level(crate::Level::ERROR);
+
+        assert_eq!(msg.level(), crate::Level::WARN);
+        assert_eq!(converted.level(), crate::Level::WARN);
+        assert_eq!(explicit.level(), crate::Level::ERROR);
+        // Errors which already exist keep their level
+        assert_eq!(err.level(), crate::Level::ERROR);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn resources_are_emitted_with_the_standard_names() {
+        let _serial = serial();
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .with_resource("user", 42);
+        assert_eq!(err.resource(), Some(("user", "42")));
+        let (_, events) = capture(|| {
+            let mut err = err;
+            err.log();
+        });
+        let event = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(event.field("resource.type"), Some("user"));
+        assert_eq!(event.field("resource.id"), Some("42"));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_event_is_emitted_within_the_span_it_was_given() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let span = tracing::info_span!("request");
+            let _entered = tracing::info_span!("callback").entered();
+            let mut err: Error =
+                DetailedError::open(io_error(), PublicError::NotFound, "Users").with_span(span);
+            err.log();
+
+            let mut current: Error =
+                DetailedError::open(io_error(), PublicError::NotFound, "Users");
+            current.log();
+        });
+        let spans: Vec<Option<&str>> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| event.span.as_deref())
+            .collect();
+        assert_eq!(spans, [Some("request"), Some("callback")]);
+    }
+
+    #[test]
+    fn a_response_can_be_projected_per_call() {
+        use crate::ToResponse;
+
+        let err: Error = DetailedError::msg("missing", PublicError::NotFound, "Users");
+        let terse = err.to_response_with(|public| format!("{:?}", public));
+        let verbose = err.to_response_with(|public| (public.status_code(), public.to_response()));
+        assert_eq!(terse, "NotFound");
+        assert_eq!(verbose, (404, "not found"));
+        assert_eq!(err.to_response(), "not found");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn windows_paths_are_normalized_unless_disabled() {
+        let _serial = serial();
+        let log = || {
+            let (_, events) = capture(|| {
+                let mut err: Error = DetailedError::new(
+                    io_error(),
+                    PublicError::NotFound,
+                    None::<&str>,
+                    "Users",
+                    crate::Level::WARN,
+                    "src\\users\\mod.rs",
+                    7,
+                    module_path!(),
+                );
+                err.log();
+            });
+            let event = events.into_iter().find(|event| event.error).unwrap();
+            event.field("file").unwrap().to_string()
+        };
+        assert_eq!(log(), "src/users/mod.rs");
+
+        crate::set_normalize_paths(false);
+        let file = log();
+        crate::set_normalize_paths(true);
+        assert_eq!(file, "src\\users\\mod.rs");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_http_context_is_emitted_with_the_standard_names() {
+        let _serial = serial();
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .with_http_context(crate::HttpContext {
+                method: "GET".to_string(),
+                path: "/users/42".to_string(),
+                remote_ip: Some([127, 0, 0, 1].into()),
+                ..Default::default()
+            });
+        let (_, events) = capture(|| {
+            let mut err = err;
+            err.log();
+        });
+        let event = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(event.field("http.method"), Some("GET"));
+        assert_eq!(event.field("http.path"), Some("/users/42"));
+        assert_eq!(event.field("http.remote_ip"), Some("127.0.0.1"));
+        assert_eq!(event.field("http.query"), None);
+        assert_eq!(event.field("http.user_agent"), None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn recovered_errors_are_only_logged_once() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            let inner = crate::InnerError::new(err);
+            let mut recovered = Error::recover(inner).unwrap();
+            assert!(recovered.is_logged());
+            recovered.log();
+        });
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_public_error_variant_is_compact() {
+        // The name is only read through the `Debug` impl
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        enum Invalid {
+            Field { name: &'static str },
+            Named,
+        }
+
+        impl crate::ToResponse for Invalid {
+            type Response = ();
+
+            fn to_response(&self) -> Self::Response {}
+
+            fn variant_name(&self) -> Option<&'static str> {
+                match self {
+                    Invalid::Field { .. } => None,
+                    Invalid::Named => Some("named"),
+                }
+            }
+        }
+
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            for public in [Invalid::Field { name: "email" }, Invalid::Named] {
+                let io = io_error();
+                let _err: DetailedError<Invalid, &str> = crate::w!(io, public, "Users");
+            }
+        });
+        let variants: Vec<&str> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| event.field("public_error_variant").unwrap())
+            .collect();
+        assert_eq!(variants, ["Field", "named"]);
+        assert_eq!(
+            events[0].field("public_error"),
+            Some(r#"Field { name: "email" }"#)
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn failed_best_effort_operations_are_logged_once() {
+        use crate::DetailedResultExt;
+
+        let _serial = serial();
+        let opened: Result<u32, Error> = Err(DetailedError::open(
+            io_error(),
+            PublicError::NotFound,
+            "Users",
+        ));
+        let (loaded, events) = capture(|| {
+            let io = io_error();
+            let logged: Result<u32, Error> = Err(crate::w!(io, PublicError::NotFound, "Users"));
+            let ok: Result<u32, Error> = Ok(7);
+            [logged.ok_logged(), opened.ok_logged(), ok.ok_logged()]
+        });
+        assert_eq!(loaded, [None, None, Some(7)]);
+        assert_eq!(events.iter().filter(|event| event.error).count(), 2);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_fields_past_the_limit_are_truncated() {
+        let _serial = serial();
+        crate::set_max_fields(Some(2));
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let _err: Error = crate::w!(
+                io, PublicError::NotFound, "Users", "loading",
+                "d" => 4, "b" => 2, "a" => 1, "c" => 3
+            );
+        });
+        crate::set_max_fields(None);
+
+        let event = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(event.field("a"), Some("1"));
+        assert_eq!(event.field("b"), Some("2"));
+        assert_eq!(event.field("c"), None);
+        assert_eq!(event.field("d"), None);
+        assert_eq!(event.field("fields_truncated"), Some("2"));
+    }
+
+    #[test]
+    fn the_response_can_be_split_into_its_parts() {
+        #[derive(Debug)]
+        struct RateLimited;
+
+        impl crate::ToResponse for RateLimited {
+            type Response = &'static str;
+
+            fn to_response(&self) -> Self::Response {
+                "slow down"
+            }
+
+            fn status_code(&self) -> u16 {
+                429
+            }
+
+            fn headers(&self) -> Vec<(String, String)> {
+                vec![("Retry-After".to_string(), "5".to_string())]
+            }
+        }
+
+        let err: DetailedError<RateLimited, &str> =
+            DetailedError::msg("too many requests", RateLimited, "Users");
+        let (status, headers, body) = err.into_response_parts();
+        assert_eq!(status, 429);
+        assert_eq!(headers, [("Retry-After".to_string(), "5".to_string())]);
+        assert_eq!(body, "slow down");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn timeouts_and_passed_deadlines_are_flagged() {
+        use std::time::{Duration, Instant};
+
+        let _serial = serial();
+        let now = Instant::now();
+        let passed: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .with_deadline(now - Duration::from_millis(10));
+        let timed_out: Error = DetailedError::open(
+            std::io::Error::from(std::io::ErrorKind::TimedOut),
+            PublicError::NotFound,
+            "Users",
+        )
+        .context("loading the user");
+        let pending: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .with_deadline(now + Duration::from_secs(60));
+        assert!(passed.is_deadline_exceeded());
+        assert!(timed_out.is_deadline_exceeded());
+        assert!(!pending.is_deadline_exceeded());
+
+        let (_, events) = capture(|| {
+            for mut err in [passed, timed_out, pending] {
+                err.log();
+            }
+        });
+        let flags: Vec<Option<&str>> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| event.field("deadline_exceeded"))
+            .collect();
+        assert_eq!(flags, [Some("true"), Some("true"), None]);
+    }
+
+    #[test]
+    fn only_the_matching_errors_are_recovered_from() {
+        use crate::DetailedResultExt;
+        use std::io::ErrorKind;
+
+        let failed = |kind: ErrorKind| -> Result<u32, Error> {
+            Err(
+                DetailedError::open(std::io::Error::from(kind), PublicError::NotFound, "Users")
+                    .context("loading the user"),
+            )
+        };
+        let fallback = |e: &std::io::Error| (e.kind() == ErrorKind::TimedOut).then_some(0);
+
+        assert_eq!(failed(ErrorKind::TimedOut).recover_if(fallback).unwrap(), 0);
+        let err = failed(ErrorKind::NotFound)
+            .recover_if(fallback)
+            .unwrap_err();
+        assert_eq!(err.private.to_string(), "loading the user");
+        assert_eq!(err.category(), &"Users");
+        let err = failed(ErrorKind::TimedOut)
+            .recover_if(|_: &std::fmt::Error| Some(0))
+            .unwrap_err();
+        assert_eq!(err.private.to_string(), "loading the user");
+        assert_eq!(Ok::<_, Error>(7).recover_if(fallback).unwrap(), 7);
+    }
+
+    #[cfg(all(feature = "tokio", feature = "tracing"))]
+    #[test]
+    fn the_task_the_error_was_created_in_is_emitted() {
+        let _serial = serial();
+        let open = || -> Error { DetailedError::open(io_error(), PublicError::NotFound, "Users") };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let (first, second) = runtime.block_on(async {
+            let first = tokio::spawn(async move { open() });
+            let second = tokio::spawn(async move { open() });
+            (first.await.unwrap(), second.await.unwrap())
+        });
+        assert!(first.task_id().is_some());
+        assert_ne!(first.task_id(), second.task_id());
+
+        let (_, events) = capture(|| {
+            for mut err in [first, second, open()] {
+                err.log();
+            }
+        });
+        let task_ids: Vec<Option<&str>> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| event.field("task_id"))
+            .collect();
+        assert_eq!(task_ids.len(), 3);
+        assert!(task_ids[0].is_some());
+        assert_ne!(task_ids[0], task_ids[1]);
+        assert_eq!(task_ids[2], None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn errors_are_only_retryable_when_marked_as_such() {
+        use std::time::Duration;
+
+        let _serial = serial();
+        let open = || -> Error { DetailedError::open(io_error(), PublicError::NotFound, "Users") };
+        let default = open();
+        let retryable = open().with_retryable(true);
+        let after = open().with_retry_after(Duration::from_secs(2));
+        assert!(!default.is_retryable());
+        assert_eq!(default.retry_after(), None);
+        assert!(retryable.is_retryable());
+        assert_eq!(retryable.retry_after(), None);
+        assert!(after.is_retryable());
+        assert_eq!(after.retry_after(), Some(Duration::from_secs(2)));
+
+        let (_, events) = capture(|| {
+            for mut err in [default, retryable, after] {
+                err.log();
+            }
+        });
+        let hints: Vec<(Option<&str>, Option<&str>)> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| (event.field("retryable"), event.field("retry_after_ms")))
+            .collect();
+        assert_eq!(
+            hints,
+            [
+                (Some("false"), None),
+                (Some("true"), None),
+                (Some("true"), Some("2000"))
+            ]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_target_can_be_the_callers_module() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            for target in [Some(module_path!()), None] {
+                let mut err: Error =
+                    DetailedError::open(io_error(), PublicError::NotFound, "Users");
+                if let Some(target) = target {
+                    err = err.with_target(target);
+                }
+                err.log();
+            }
+            let _built: Error = DetailedError::builder()
+                .private(io_error())
+                .public(PublicError::NotFound)
+                .category("Users")
+                .target("my_app::users")
+                .build();
+        });
+        let targets: Vec<&str> = events
+            .iter()
+            .filter(|event| event.error)
+            .map(|event| event.target.as_str())
+            .collect();
+        assert_eq!(targets, [module_path!(), "api_error", "my_app::users"]);
+    }
+
+    #[test]
+    fn the_chain_goes_from_the_outermost_context_to_the_root_cause() {
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+            .context("loading the user")
+            .context("handling the request");
+        let chain: Vec<String> = err.chain().map(|cause| cause.to_string()).collect();
+        assert_eq!(
+            chain,
+            [
+                "handling the request",
+                "loading the user",
+                "connection reset"
+            ]
+        );
+        let root = err.root_cause();
+        assert_eq!(root.to_string(), "connection reset");
+        assert!(root.downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn downcasting_finds_the_private_error() {
+        let err: Error =
+            DetailedError::open(io_error(), PublicError::NotFound, "Users").context("loading");
+        let io = err.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::Other);
+        assert!(err.downcast_ref::<std::fmt::Error>().is_none());
+
+        let io = err.downcast::<std::io::Error>().unwrap();
+        assert_eq!(io.to_string(), "connection reset");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn a_failed_downcast_gives_back_the_error_without_logging_it() {
+        let _serial = serial();
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users");
+        let id = err.error_id();
+        let (err, events) = capture(|| err.downcast::<std::fmt::Error>().unwrap_err());
+        assert!(events.is_empty());
+        assert_eq!(err.error_id(), id);
+        assert!(!err.is_logged());
+        assert_eq!(err.public, PublicError::NotFound);
+        assert!(err.downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn os_errors_are_found_in_the_chain() {
+        let err: Error = DetailedError::open(
+            std::io::Error::from_raw_os_error(2),
+            PublicError::NotFound,
+            "Users",
+        )
+        .context("opening the config");
+        assert_eq!(err.os_error(), Some(2));
+
+        let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users");
+        assert_eq!(err.os_error(), None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn children_are_linked_to_their_parent() {
+        let _serial = serial();
+        let parent: Error = DetailedError::open(io_error(), PublicError::NotFound, "Database");
+        let parent_id = parent.error_id().to_string();
+        let (_, events) = capture(|| {
+            for case in [crate::KeyCase::Snake, crate::KeyCase::Camel] {
+                crate::set_key_case(case);
+                let mut child: Error =
+                    DetailedError::open(io_error(), PublicError::NotFound, "Users")
+                        .with_parent(&parent);
+                child.log();
+            }
+            crate::set_key_case(crate::KeyCase::Snake);
+        });
+        let errors: Vec<_> = events.iter().filter(|event| event.error).collect();
+        assert_eq!(errors[0].field("parent_error_id"), Some(parent_id.as_str()));
+        assert_eq!(errors[1].field("parentErrorId"), Some(parent_id.as_str()));
+        assert_ne!(errors[0].field("error_id"), Some(parent_id.as_str()));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn relogging_emits_the_fields_added_since() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            let mut err = err.with_field("user_id", 42);
+            err.relog();
+        });
+        let errors: Vec<_> = events.iter().filter(|event| event.error).collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field("user_id"), None);
+        assert_eq!(errors[1].field("user_id"), Some("42"));
+        assert_eq!(errors[0].field("error_id"), errors[1].field("error_id"));
+    }
+
+    #[cfg(all(feature = "tracing", feature = "aggregate"))]
+    #[test]
+    fn relogging_is_only_counted_once() {
+        let _serial = serial();
+        crate::start_category_counts(std::time::Duration::from_secs(3600));
+        crate::flush_category_counts();
+        let io = io_error();
+        let mut err: Error = crate::w!(io, PublicError::NotFound, "Relogged");
+        err.relog();
+        err.relog();
+        let (_, events) = capture(crate::flush_category_counts);
+        let counts = events[0].field("counts").unwrap();
+        assert!(counts.contains(r#""Relogged.WARN": 1"#), "{}", counts);
+    }
+
+    #[test]
+    fn context_can_be_added_to_a_result() {
+        use crate::DetailedContext;
+
+        let opened = || -> Result<(), Error> {
+            Err(DetailedError::open(
+                io_error(),
+                PublicError::NotFound,
+                "Users",
+            ))
+        };
+        let err = opened().context("loading the user").unwrap_err();
+        assert_eq!(err.public, PublicError::NotFound);
+        assert_eq!(*err.category(), "Users");
+        assert_eq!(err.private.to_string(), "loading the user");
+        assert_eq!(err.root_cause().to_string(), "connection reset");
+
+        let err = opened()
+            .with_context(|| format!("loading user {}", 42))
+            .unwrap_err();
+        assert_eq!(err.public, PublicError::NotFound);
+        assert_eq!(*err.category(), "Users");
+        assert_eq!(err.private.to_string(), "loading user 42");
+
+        let ok: Result<u8, Error> = Ok(7);
+        let ok = ok.with_context(|| -> &str { unreachable!("only built for an error") });
+        assert_eq!(ok.unwrap(), 7);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn adding_context_to_a_result_does_not_log_it_again() {
+        use crate::DetailedContext;
+
+        let _serial = serial();
+        let (err, events) = capture(|| {
+            let io = io_error();
+            let result: Result<(), Error> = Err(crate::w!(io, PublicError::NotFound, "Users"));
+            result.context("loading the user").unwrap_err()
+        });
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+
+        let (_, events) = capture(|| {
+            let mut err = err;
+            err.relog();
+        });
+        assert_eq!(events[0].field("message"), Some("loading the user"));
+        assert_eq!(events[0].field("errors"), Some(r#"["connection reset"]"#));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[derive(Debug)]
+    enum AppError {
+        Detailed(Error),
+    }
+
+    #[cfg(feature = "tracing")]
+    impl From<Error> for AppError {
+        fn from(err: Error) -> Self {
+            AppError::Detailed(err)
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn into_variant_wraps_the_logged_error() {
+        let _serial = serial();
+        let (app, events) = capture(|| {
+            let io = io_error();
+            let err: Error = crate::w!(io, PublicError::NotFound, "Users", "loading the user");
+            let id = err.error_id();
+            (err.into_variant::<AppError>(), id)
+        });
+        let (AppError::Detailed(err), id) = app;
+        assert_eq!(err.error_id(), id);
+        assert!(err.is_logged());
+        assert_eq!(err.public, PublicError::NotFound);
+        drop(err);
+        let logged: Vec<_> = events.iter().filter(|event| event.error).collect();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].field("message"), Some("loading the user"));
+        assert_eq!(logged[0].field("error_id"), Some(id.to_string().as_str()));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_remediation_link_is_emitted() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let mut err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users")
+                .with_remediation("https://runbooks.example.com/users");
+            assert_eq!(
+                err.remediation(),
+                Some("https://runbooks.example.com/users")
+            );
+            err.log();
+        });
+        let logged = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(
+            logged.field("remediation_url"),
+            Some("https://runbooks.example.com/users")
+        );
+    }
+}
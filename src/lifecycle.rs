@@ -0,0 +1,218 @@
+//! Errors created up front for an operation which might still recover, eg. one which is being
+//! retried.
+//!
+//! [`DetailedError::open`] emits an `error opened` event straight away, and the error is only
+//! logged as normal if the operation goes on to fail. When it recovers an `error recovered` event
+//! is emitted instead. Both events have the `error_id` of the error, so the lifecycle can be
+//! followed from one to the other.
+
+use crate::emit::{emit, Location};
+use crate::{config, DetailedError, InnerError, Level, ToResponse, LOG_TARGET};
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display};
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Create a new error without logging it, emitting an `error opened` event with
+    /// [`Level::TRACE`] instead
+    ///
+    /// The error is logged at the [default level](crate::set_default_level) once it's
+    /// [resolved](Self::resolve) with a failure, or when [`log`](Self::log) is called.
+    #[track_caller]
+    pub fn open<P>(private: P, public: Pub, category: Cat) -> Self
+    where
+        P: StdError + Send + Sync + 'static,
+    {
+        let location = std::panic::Location::caller();
+        let err = Self::from_inner(
+            InnerError::new(private),
+            Some(std::any::type_name::<P>()),
+            public,
+            category,
+            config::default_level(),
+            location.file(),
+            location.line(),
+            "",
+            HashMap::with_capacity(0),
+        );
+        err.emit_lifecycle(Level::TRACE, "error opened");
+        err
+    }
+
+    /// Finish an error which was [opened](Self::open) with the outcome of the operation
+    ///
+    /// If it recovered an `error recovered` event is emitted with [`Level::DEBUG`] and the error
+    /// is discarded, otherwise the failure is added as context and the error is logged as normal.
+    pub fn resolve<T, E>(self, outcome: Result<T, E>) -> Result<T, Self>
+    where
+        E: Display + Send + Sync + 'static,
+    {
+        match outcome {
+            Ok(t) => {
+                if !self.meta.has_logged.get() {
+                    self.emit_lifecycle(Level::DEBUG, "error recovered");
+                    self.meta.has_logged.set(true);
+                }
+                Ok(t)
+            }
+            Err(e) => {
+                let mut err = self.context(e);
+                err.log();
+                Err(err)
+            }
+        }
+    }
+
+    /// Log the error only if `pred` says so, eg. a sampling decision or a feature flag which is
+    /// only known at the boundary, otherwise it's considered handled without being logged
+    ///
+    /// With the `aggregate` or `metrics` features a suppressed error is still included in the
+    /// category counts.
+    ///
+    /// This has no effect once the error has been logged.
+    pub fn emit_if<F>(&mut self, pred: F)
+    where
+        F: FnOnce(&Self) -> bool,
+    {
+        if self.meta.has_logged.get() {
+            return;
+        }
+        if pred(self) {
+            self.log();
+        } else {
+            self.count(self.meta.level);
+            self.meta.has_logged.set(true);
+        }
+    }
+
+    fn emit_lifecycle(&self, level: Level, message: &str) {
+        let error_id = self.meta.id.to_string();
+        let category = self.meta.category.to_string();
+        let location = Location {
+            target: LOG_TARGET,
+            file: self.meta.file,
+            line: self.meta.line,
+            module: self.meta.module,
+        };
+        emit(
+            level,
+            &location,
+            &[
+                ("error_id", &error_id),
+                ("category", &category),
+                ("message", &message),
+            ],
+        );
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::test_support::{capture, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    fn open() -> Error {
+        DetailedError::open(io_error(), PublicError::NotFound, "Users")
+    }
+
+    #[test]
+    fn recovering_emits_the_opened_and_recovered_events() {
+        let _serial = serial();
+        let (id, events) = capture(|| {
+            let err = open();
+            let id = err.error_id();
+            assert_eq!(err.resolve(Ok::<_, String>(7)).unwrap(), 7);
+            id
+        });
+        let id = id.to_string();
+        let lifecycle: Vec<(Option<&str>, Option<&str>)> = events
+            .iter()
+            .map(|event| (event.field("message"), event.field("error_id")))
+            .collect();
+        assert_eq!(
+            lifecycle,
+            [
+                (Some("error opened"), Some(id.as_str())),
+                (Some("error recovered"), Some(id.as_str()))
+            ]
+        );
+        assert_eq!(events[0].level, crate::Level::TRACE);
+        assert_eq!(events[1].level, crate::Level::DEBUG);
+        assert!(events.iter().all(|event| !event.error));
+    }
+
+    #[test]
+    fn failing_logs_the_error_once() {
+        let _serial = serial();
+        let err = open();
+        let id = err.error_id();
+        let (err, events) = capture(|| {
+            let mut err = err
+                .resolve(Err::<(), _>("the retries ran out"))
+                .unwrap_err();
+            err.log();
+            err
+        });
+        assert_eq!(err.error_id(), id);
+        assert!(err.is_logged());
+        let logged: Vec<_> = events.iter().filter(|event| event.error).collect();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].field("message"), Some("the retries ran out"));
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn only_logs_when_the_predicate_says_so() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let mut err = open();
+            err.emit_if(|_| true);
+            assert!(err.is_logged());
+        });
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+
+        let (_, events) = capture(|| {
+            let mut err = open();
+            err.emit_if(|err| err.category() != &"Users");
+            assert!(err.is_logged());
+            err.log();
+        });
+        assert_eq!(events.iter().filter(|event| event.error).count(), 0);
+    }
+
+    #[test]
+    fn does_nothing_once_logged() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let mut err = open();
+            err.log();
+            let mut called = false;
+            err.emit_if(|_| {
+                called = true;
+                true
+            });
+            assert!(!called);
+        });
+        assert_eq!(events.iter().filter(|event| event.error).count(), 1);
+    }
+
+    #[cfg(feature = "aggregate")]
+    #[test]
+    fn suppressed_errors_are_still_counted() {
+        let _serial = serial();
+        crate::start_category_counts(std::time::Duration::from_secs(3600));
+        crate::flush_category_counts();
+        let mut err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Sampled");
+        err.emit_if(|_| false);
+        let (_, events) = capture(crate::flush_category_counts);
+        let counts = events[0].field("counts").unwrap();
+        assert!(counts.contains(r#""Sampled.ERROR": 1"#), "{}", counts);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{config, DetailedError, ToResponse};
+
+use std::fmt::{Debug, Display};
+
+/// A public error which can be translated for the client, see [`DetailedError::client_message`]
+///
+/// Errors which only have the one message can use the default impl, which always falls back to
+/// their [`Display`] impl.
+pub trait LocalizedResponse: Display {
+    /// The message in the language `lang` (eg. `fr-CA`), if there's a translation for it
+    fn localized_message(&self, _lang: &str) -> Option<String> {
+        None
+    }
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// The message to show the client, in the language `lang` where possible, with the
+    /// [`ErrorId`](crate::ErrorId) included so that they can reference it
+    /// (eg. `Something went wrong. (ref: <id>)`)
+    ///
+    /// See [`set_client_message_template`](crate::set_client_message_template) for how the two are
+    /// combined.
+    pub fn client_message(&self, lang: Option<&str>) -> String
+    where
+        Pub: LocalizedResponse,
+    {
+        let message = lang
+            .and_then(|lang| self.public.localized_message(lang))
+            .unwrap_or_else(|| self.public.to_string());
+        config::render_template(
+            &config::client_message_template(),
+            |placeholder| match placeholder {
+                "message" => Some(message.clone()),
+                "ref" => Some(self.meta.id.to_string()),
+                _ => None,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, serial};
+
+    use std::fmt;
+
+    #[derive(Debug)]
+    struct Unavailable;
+
+    impl ToResponse for Unavailable {
+        type Response = &'static str;
+
+        fn to_response(&self) -> Self::Response {
+            "unavailable"
+        }
+    }
+
+    impl Display for Unavailable {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("Something went wrong.")
+        }
+    }
+
+    impl LocalizedResponse for Unavailable {
+        fn localized_message(&self, lang: &str) -> Option<String> {
+            (lang == "fr").then(|| "Une erreur s'est produite.".to_string())
+        }
+    }
+
+    #[test]
+    fn the_ref_is_embedded_in_the_message() {
+        let _serial = serial();
+        let err: DetailedError<Unavailable, &str> =
+            DetailedError::open(io_error(), Unavailable, "Users");
+        let id = err.error_id();
+
+        assert_eq!(
+            err.client_message(None),
+            format!("Something went wrong. (ref: {})", id)
+        );
+        assert_eq!(
+            err.client_message(Some("fr")),
+            format!("Une erreur s'est produite. (ref: {})", id)
+        );
+        assert_eq!(
+            err.client_message(Some("de")),
+            format!("Something went wrong. (ref: {})", id)
+        );
+
+        crate::set_client_message_template("[{ref}] {message}");
+        let message = err.client_message(None);
+        crate::set_client_message_template("{message} (ref: {ref})");
+        assert_eq!(message, format!("[{}] Something went wrong.", id));
+    }
+}
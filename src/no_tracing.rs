@@ -0,0 +1,102 @@
+//! Stand-ins for the parts of `tracing` which are used throughout the crate, for when the
+//! `tracing` feature is disabled.
+//!
+//! Errors keep their level so that it can still be inspected, but nothing is ever emitted.
+
+use core::fmt::{self, Debug, Display};
+use core::str::FromStr;
+
+/// The severity of an error, mirroring `tracing::Level` (which this is when the `tracing` feature
+/// is enabled) so the more severe levels compare as less than the others
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Level(Inner);
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Inner {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub const ERROR: Level = Level(Inner::Error);
+    pub const WARN: Level = Level(Inner::Warn);
+    pub const INFO: Level = Level(Inner::Info);
+    pub const DEBUG: Level = Level(Inner::Debug);
+    pub const TRACE: Level = Level(Inner::Trace);
+
+    pub fn as_str(&self) -> &'static str {
+        match self.0 {
+            Inner::Error => "ERROR",
+            Inner::Warn => "WARN",
+            Inner::Info => "INFO",
+            Inner::Debug => "DEBUG",
+            Inner::Trace => "TRACE",
+        }
+    }
+}
+
+impl Debug for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returned when parsing a [`Level`] which isn't one of the known levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLevelError;
+
+impl Display for ParseLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("error parsing level: expected one of \"error\", \"warn\", \"info\", \"debug\" or \"trace\"")
+    }
+}
+
+impl core::error::Error for ParseLevelError {}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            Level::ERROR,
+            Level::WARN,
+            Level::INFO,
+            Level::DEBUG,
+            Level::TRACE,
+        ]
+        .into_iter()
+        .find(|level| level.as_str().eq_ignore_ascii_case(s))
+        .ok_or(ParseLevelError)
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) mod field {
+    use core::fmt::{Debug, Display};
+
+    /// Anything can be a field, as it's never recorded
+    pub(crate) trait Value {}
+
+    impl<T: ?Sized> Value for T {}
+
+    pub(crate) struct DebugValue<T: Debug>(#[allow(dead_code)] T);
+
+    pub(crate) struct DisplayValue<T: Display>(#[allow(dead_code)] T);
+
+    pub(crate) fn debug<T: Debug>(t: T) -> DebugValue<T> {
+        DebugValue(t)
+    }
+
+    pub(crate) fn display<T: Display>(t: T) -> DisplayValue<T> {
+        DisplayValue(t)
+    }
+}
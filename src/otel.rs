@@ -0,0 +1,296 @@
+//! Support for the span conventions of [`tracing-opentelemetry`](https://docs.rs/tracing-opentelemetry)
+//!
+//! Fields can only be recorded onto a span if they were declared when it was created, so for the
+//! span status to be set it needs to declare:
+//!
+//! ```
+//! let span = tracing::info_span!(
+//!     "request",
+//!     otel.status_code = tracing::field::Empty,
+//!     otel.status_description = tracing::field::Empty,
+//! );
+//! ```
+
+use crate::emit::{emit, Location};
+use crate::{config, DetailedError, ToResponse};
+
+use tracing::field::display;
+use tracing::{Level, Span};
+
+use std::fmt::{Debug, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The target used for the events emitted by [`DetailedError::record_otel_exception`]
+pub const OTEL_TARGET: &str = "api_error::otel";
+
+static RECORD_SPAN_STATUS: AtomicBool = AtomicBool::new(false);
+
+/// Whether [`DetailedError::set_otel_span_status`] should be called whenever an error is logged
+pub fn set_record_otel_span_status(record: bool) {
+    RECORD_SPAN_STATUS.store(record, Ordering::Relaxed);
+}
+
+pub(crate) fn record_span_status() -> bool {
+    RECORD_SPAN_STATUS.load(Ordering::Relaxed)
+}
+
+struct ServiceMetadata {
+    name: &'static str,
+    version: &'static str,
+    environment: &'static str,
+}
+
+static SERVICE_METADATA: RwLock<Option<ServiceMetadata>> = RwLock::new(None);
+
+/// Set the service which is running, this is included in the resource of every
+/// [`OtlpLogRecord`] as `service.name`, `service.version` and `deployment.environment`
+pub fn set_service_metadata(name: &'static str, version: &'static str, environment: &'static str) {
+    *SERVICE_METADATA.write().unwrap_or_else(|e| e.into_inner()) = Some(ServiceMetadata {
+        name,
+        version,
+        environment,
+    });
+}
+
+/// An error in the shape of the OpenTelemetry logs data model, for exporting it over OTLP
+///
+/// The resource holds the [service metadata](set_service_metadata) and the
+/// [build info](crate::set_build_info) (as `build.commit` and `build.timestamp`), whichever of
+/// them have been set, so that the backend can group the records by the service they came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpLogRecord {
+    pub time_unix_nano: u64,
+    pub severity_number: u8,
+    pub severity_text: &'static str,
+    pub body: String,
+    pub attributes: Vec<(String, String)>,
+    pub resource: Vec<(String, String)>,
+}
+
+fn resource_attributes() -> Vec<(String, String)> {
+    let mut resource = Vec::new();
+    if let Some(service) = &*SERVICE_METADATA.read().unwrap_or_else(|e| e.into_inner()) {
+        resource.push(("service.name".to_string(), service.name.to_string()));
+        resource.push(("service.version".to_string(), service.version.to_string()));
+        resource.push((
+            "deployment.environment".to_string(),
+            service.environment.to_string(),
+        ));
+    }
+    if let Some((commit, built_at)) = config::build_info() {
+        resource.push(("build.commit".to_string(), commit.to_string()));
+        resource.push(("build.timestamp".to_string(), built_at.to_string()));
+    }
+    resource
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Mark the current span as errored, with the [name of the public error](ToResponse::variant_name)
+    /// as its description
+    ///
+    /// Traces are often kept somewhere less trusted than the logs, so the [`Debug`]
+    /// representation of the public error (and any data it holds) is left out.
+    pub fn set_otel_span_status(&self) {
+        let span = Span::current();
+        span.record("otel.status_code", "ERROR");
+        span.record(
+            "otel.status_description",
+            crate::public_variant(&self.public).as_str(),
+        );
+    }
+
+    /// Record the private error as an exception on the current span, following the
+    /// OpenTelemetry semantic conventions (`exception.type`, `exception.message` and
+    /// `exception.stacktrace`)
+    ///
+    /// This is emitted as an event under the [`OTEL_TARGET`] target, which
+    /// `tracing-opentelemetry` attaches to the span it's emitted within.
+    pub fn record_otel_exception(&self) {
+        let exception_type = self.meta.private_type.unwrap_or("message");
+        let stacktrace = self.stacktrace();
+        let message = display(&self.private);
+        let location = Location {
+            target: OTEL_TARGET,
+            file: self.meta.file,
+            line: self.meta.line,
+            module: self.meta.module,
+        };
+        emit(
+            Level::ERROR,
+            &location,
+            &[
+                ("exception.type", &exception_type),
+                ("exception.message", &message),
+                ("exception.stacktrace", &stacktrace),
+                ("message", &"exception"),
+            ],
+        );
+    }
+
+    /// Build the [`OtlpLogRecord`] for this error, the attributes follow the same semantic
+    /// conventions as [`record_otel_exception`](Self::record_otel_exception) along with the
+    /// `error_id`, `category`, `public_error` and any additional fields
+    pub fn to_otlp_log_record(&self) -> OtlpLogRecord {
+        let level = self.meta.level;
+        let severity_number = match level {
+            Level::TRACE => 1,
+            Level::DEBUG => 5,
+            Level::INFO => 9,
+            Level::WARN => 13,
+            Level::ERROR => 17,
+        };
+        let time_unix_nano = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+
+        let mut attributes = vec![
+            ("error_id".to_string(), self.meta.id.to_string()),
+            ("category".to_string(), self.meta.category.to_string()),
+            ("public_error".to_string(), format!("{:?}", self.public)),
+            (
+                "exception.type".to_string(),
+                self.meta.private_type.unwrap_or("message").to_string(),
+            ),
+            ("exception.message".to_string(), self.private.to_string()),
+            ("code.filepath".to_string(), self.meta.file.to_string()),
+            ("code.lineno".to_string(), self.meta.line.to_string()),
+        ];
+        if !self.meta.module.is_empty() {
+            attributes.push(("code.namespace".to_string(), self.meta.module.to_string()));
+        }
+        if let Some(stacktrace) = self.stacktrace() {
+            attributes.push(("exception.stacktrace".to_string(), stacktrace));
+        }
+        let mut fields: Vec<_> = self.meta.fields.iter().collect();
+        fields.sort_by_key(|(key, _)| *key);
+        attributes.extend(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+
+        OtlpLogRecord {
+            time_unix_nano,
+            severity_number,
+            severity_text: level.as_str(),
+            body: self.private.to_string(),
+            attributes,
+            resource: resource_attributes(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{capture, io_error, serial};
+    use crate::{DetailedError, ToResponse};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::Registry;
+
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    enum PublicError {
+        #[allow(dead_code)]
+        Forbidden { user: String },
+    }
+
+    impl ToResponse for PublicError {
+        type Response = &'static str;
+
+        fn to_response(&self) -> Self::Response {
+            "forbidden"
+        }
+    }
+
+    /// The fields which are recorded onto spans after they're created
+    #[derive(Clone, Default)]
+    struct Recorded(Arc<Mutex<BTreeMap<String, String>>>);
+
+    impl Visit for &Recorded {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Recorded {
+        fn on_new_span(&self, _attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {}
+
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            values.record(&mut &*self);
+        }
+    }
+
+    #[test]
+    fn sets_the_span_status() {
+        let _serial = serial();
+        let recorded = Recorded::default();
+        let subscriber = Registry::default().with(recorded.clone());
+        let err: DetailedError<PublicError, &str> = DetailedError::open(
+            io_error(),
+            PublicError::Forbidden {
+                user: "alice".to_string(),
+            },
+            "Users",
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "request",
+                otel.status_code = tracing::field::Empty,
+                otel.status_description = tracing::field::Empty,
+            );
+            let _entered = span.enter();
+            err.set_otel_span_status();
+        });
+
+        let recorded = recorded.0.lock().unwrap();
+        assert_eq!(recorded["otel.status_code"], "ERROR");
+        assert_eq!(recorded["otel.status_description"], "Forbidden");
+    }
+
+    #[test]
+    fn records_the_exception_as_an_event() {
+        let _serial = serial();
+        let err: DetailedError<PublicError, &str> = DetailedError::open(
+            io_error(),
+            PublicError::Forbidden {
+                user: "alice".to_string(),
+            },
+            "Users",
+        );
+        crate::set_key_case(crate::KeyCase::Camel);
+        let (_, events) = capture(|| err.record_otel_exception());
+        crate::set_key_case(crate::KeyCase::Snake);
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.target, crate::OTEL_TARGET);
+        assert_eq!(event.level, tracing::Level::ERROR);
+        assert_eq!(event.field("exception.type"), Some("std::io::error::Error"));
+        assert_eq!(event.field("exception.message"), Some("connection reset"));
+        assert!(event.field("exception.stacktrace").is_some());
+        assert_eq!(event.field("message"), Some("exception"));
+    }
+}
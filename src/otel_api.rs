@@ -0,0 +1,132 @@
+//! Recording errors onto spans through the [`opentelemetry`](https://docs.rs/opentelemetry) API
+//! directly, for services which don't go through `tracing-opentelemetry` (see the `otel` feature
+//! for those which do)
+
+use crate::{DetailedError, ToResponse};
+
+use opentelemetry::trace::{Span, Status};
+use opentelemetry::KeyValue;
+
+use std::fmt::{Debug, Display};
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Record the private error onto `span` as an `exception` event following the OpenTelemetry
+    /// semantic conventions, and mark the span's status as an error with the
+    /// [name of the public error](ToResponse::variant_name) as its description
+    ///
+    /// As with [`set_otel_span_status`](Self::set_otel_span_status) the [`Debug`] representation
+    /// of the public error (and any data it holds) is left out of the trace.
+    ///
+    /// The event has the type of the private error as `exception.type` (or `message` when it's
+    /// not known), its whole chain as `exception.message` and, with `anyhow`, the backtrace it
+    /// captured as `exception.stacktrace`.
+    pub fn record_otel<S: Span>(&self, span: &mut S) {
+        let mut attributes = vec![
+            KeyValue::new(
+                "exception.type",
+                self.meta.private_type.unwrap_or("message"),
+            ),
+            KeyValue::new("exception.message", format!("{:#}", self.private)),
+        ];
+        if let Some(stacktrace) = self.stacktrace() {
+            attributes.push(KeyValue::new("exception.stacktrace", stacktrace));
+        }
+        span.add_event("exception", attributes);
+        span.set_status(Status::error(crate::public_variant(&self.public)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, PublicError};
+
+    use opentelemetry::trace::SpanContext;
+    use opentelemetry::Key;
+
+    use std::borrow::Cow;
+    use std::time::SystemTime;
+
+    /// A span which keeps everything recorded onto it
+    struct Recording {
+        context: SpanContext,
+        events: Vec<(String, Vec<KeyValue>)>,
+        attributes: Vec<KeyValue>,
+        status: Status,
+    }
+
+    impl Span for Recording {
+        fn add_event_with_timestamp<T>(
+            &mut self,
+            name: T,
+            _timestamp: SystemTime,
+            attributes: Vec<KeyValue>,
+        ) where
+            T: Into<Cow<'static, str>>,
+        {
+            self.events.push((name.into().into_owned(), attributes));
+        }
+
+        fn span_context(&self) -> &SpanContext {
+            &self.context
+        }
+
+        fn is_recording(&self) -> bool {
+            true
+        }
+
+        fn set_attribute(&mut self, attribute: KeyValue) {
+            self.attributes.push(attribute);
+        }
+
+        fn set_status(&mut self, status: Status) {
+            self.status = status;
+        }
+
+        fn update_name<T>(&mut self, _new_name: T)
+        where
+            T: Into<Cow<'static, str>>,
+        {
+        }
+
+        fn add_link(&mut self, _span_context: SpanContext, _attributes: Vec<KeyValue>) {}
+
+        fn end_with_timestamp(&mut self, _timestamp: SystemTime) {}
+    }
+
+    #[test]
+    fn records_the_exception_and_the_status() {
+        let err: DetailedError<PublicError, &str> =
+            DetailedError::open(io_error(), PublicError::NotFound, "Users").context("loading");
+        let mut span = Recording {
+            context: SpanContext::empty_context(),
+            events: Vec::new(),
+            attributes: Vec::new(),
+            status: Status::Unset,
+        };
+        err.record_otel(&mut span);
+
+        assert_eq!(span.status, Status::error("NotFound"));
+        assert!(span.attributes.is_empty());
+        assert_eq!(span.events.len(), 1);
+        let (name, attributes) = &span.events[0];
+        assert_eq!(name, "exception");
+        let attribute = |key: &'static str| {
+            attributes
+                .iter()
+                .find(|kv| kv.key == Key::from_static_str(key))
+                .map(|kv| kv.value.to_string())
+        };
+        assert_eq!(
+            attribute("exception.type").as_deref(),
+            Some("std::io::error::Error")
+        );
+        let message = attribute("exception.message").unwrap();
+        assert!(message.starts_with("loading"));
+        assert!(message.contains("connection reset"));
+    }
+}
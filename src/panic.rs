@@ -0,0 +1,120 @@
+use crate::{config, DetailedError, InnerError, ToResponse};
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::panic::{catch_unwind, Location, UnwindSafe};
+
+/// The private error of a [`DetailedError`] created by [`catch_panic`], holding the message the
+/// closure panicked with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicError(pub String);
+
+impl Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked: {}", self.0)
+    }
+}
+
+impl StdError for PanicError {}
+
+impl PanicError {
+    fn from_payload(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "Box<dyn Any>".to_string(),
+            },
+        };
+        PanicError(message)
+    }
+}
+
+/// Run `f`, turning a panic within it into an error with `category` and `public`, so that a risky
+/// operation (eg. a plugin or FFI call) can be isolated without a process-wide panic hook
+///
+/// The error is logged straight away at the [default level](crate::set_default_level). Its
+/// private error is a [`PanicError`] with the message of the panic (when it's a `&str` or a
+/// `String`), and its location is where `catch_panic` was called as the location of the panic
+/// itself is only available to a panic hook.
+#[track_caller]
+pub fn catch_panic<T, Pub, Cat, F>(
+    category: Cat,
+    public: Pub,
+    f: F,
+) -> Result<T, DetailedError<Pub, Cat>>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+    F: FnOnce() -> T + UnwindSafe,
+{
+    let location = Location::caller();
+    catch_unwind(f).map_err(|payload| {
+        let mut err = DetailedError::from_inner(
+            InnerError::new(PanicError::from_payload(payload)),
+            Some(std::any::type_name::<PanicError>()),
+            public,
+            category,
+            config::default_level(),
+            location.file(),
+            location.line(),
+            "",
+            HashMap::with_capacity(0),
+        );
+        err.log();
+        err
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{serial, PublicError};
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    fn caught(f: impl FnOnce() + UnwindSafe) -> Error {
+        catch_panic("Plugins", PublicError::NotFound, f).unwrap_err()
+    }
+
+    fn message(err: &Error) -> &str {
+        &err.downcast_ref::<PanicError>().unwrap().0
+    }
+
+    #[test]
+    fn str_payloads_are_kept() {
+        let _serial = serial();
+        let err = caught(|| panic!("the plugin crashed"));
+        assert_eq!(message(&err), "the plugin crashed");
+        assert_eq!(err.to_string(), "panicked: the plugin crashed");
+        assert_eq!(err.category(), &"Plugins");
+        assert_eq!(err.public, PublicError::NotFound);
+        assert_eq!(err.level(), crate::Level::ERROR);
+        assert!(err.is_logged());
+    }
+
+    #[test]
+    fn string_payloads_are_kept() {
+        let _serial = serial();
+        let id = 42;
+        let err = caught(move || panic!("plugin {} crashed", id));
+        assert_eq!(message(&err), "plugin 42 crashed");
+        assert_eq!(err.category(), &"Plugins");
+    }
+
+    #[test]
+    fn other_payloads_are_named() {
+        let _serial = serial();
+        let err = caught(|| std::panic::panic_any(42u32));
+        assert_eq!(message(&err), "Box<dyn Any>");
+        assert_eq!(err.category(), &"Plugins");
+    }
+
+    #[test]
+    fn values_are_returned_as_is() {
+        let result: Result<_, Error> = catch_panic("Plugins", PublicError::NotFound, || vec![1, 2]);
+        assert_eq!(result.unwrap(), [1, 2]);
+    }
+}
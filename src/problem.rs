@@ -0,0 +1,165 @@
+use crate::{public_variant, DetailedError, ToResponse};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::fmt::{Debug, Display};
+
+/// The media type of a [`ProblemDetails`] body, for the `Content-Type` header
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// An [RFC 7807](https://www.rfc-editor.org/rfc/rfc7807) problem details body
+///
+/// The fields are serialized with the names the RFC defines, the type of the problem as `type`.
+/// It can be used as the [`ToResponse::Response`] of a public error, the JSON helpers (eg.
+/// [`DetailedError::to_response_with_debug`]) work with it as it converts into a JSON value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+}
+
+impl ProblemDetails {
+    /// A problem without a specific type, which the RFC represents as `about:blank`
+    pub fn new(status: u16, title: impl Into<String>) -> Self {
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: title.into(),
+            status,
+            detail: None,
+            instance: None,
+        }
+    }
+
+    /// A URI identifying the type of the problem, eg. to the docs describing it
+    pub fn with_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    /// An explanation of this occurrence of the problem
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// A URI identifying this occurrence of the problem
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+}
+
+impl From<ProblemDetails> for Value {
+    fn from(problem: ProblemDetails) -> Self {
+        serde_json::to_value(problem).expect("problem details always serialize")
+    }
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// The public error as a [`ProblemDetails`] body, for APIs which use
+    /// [`PROBLEM_JSON`] rather than their own shape of error
+    ///
+    /// The status is the public error's [status code](ToResponse::status_code), the title its
+    /// [variant name](ToResponse::variant_name) and the detail its [`Display`] impl. The instance
+    /// is the [`ErrorId`](crate::ErrorId) as a `urn:uuid:` URI, so it can be matched to the event
+    /// which was emitted.
+    pub fn to_problem_details(&self) -> ProblemDetails
+    where
+        Pub: Display,
+    {
+        ProblemDetails::new(self.public.status_code(), public_variant(&self.public))
+            .with_detail(self.public.to_string())
+            .with_instance(format!("urn:uuid:{}", self.meta.id))
+    }
+    /// Split the [problem details](Self::to_problem_details) response into its status code,
+    /// headers and JSON body, the same as [`into_response_parts`](Self::into_response_parts) with
+    /// the `Content-Type` set to [`PROBLEM_JSON`]
+    ///
+    /// The framework integrations only set the `Content-Type` when there isn't one already, so
+    /// this can be used to build their responses too.
+    pub fn into_problem_response_parts(self) -> (u16, Vec<(String, String)>, Value)
+    where
+        Pub: Display,
+    {
+        self.log_if_lazy();
+        let problem = self.to_problem_details();
+        let mut headers: Vec<(String, String)> = self
+            .public
+            .headers()
+            .into_iter()
+            .filter(|(name, _)| !name.eq_ignore_ascii_case("content-type"))
+            .collect();
+        headers.push(("content-type".to_string(), PROBLEM_JSON.to_string()));
+        (problem.status, headers, problem.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, PublicError};
+
+    use serde_json::json;
+
+    #[test]
+    fn uses_the_names_from_the_rfc() {
+        let problem = ProblemDetails::new(404, "NotFound")
+            .with_type("https://example.com/problems/not-found")
+            .with_detail("no user with id 42")
+            .with_instance("/users/42");
+        assert_eq!(
+            Value::from(problem),
+            json!({
+                "type": "https://example.com/problems/not-found",
+                "title": "NotFound",
+                "status": 404,
+                "detail": "no user with id 42",
+                "instance": "/users/42",
+            })
+        );
+    }
+
+    #[test]
+    fn unset_fields_are_left_out() {
+        assert_eq!(
+            Value::from(ProblemDetails::new(500, "Internal")),
+            json!({ "type": "about:blank", "title": "Internal", "status": 500 })
+        );
+    }
+
+    #[test]
+    fn the_error_is_described_by_its_public_error() {
+        let err: DetailedError<PublicError, &str> =
+            DetailedError::open(io_error(), PublicError::NotFound, "Users");
+        let id = err.error_id();
+        assert_eq!(
+            Value::from(err.to_problem_details()),
+            json!({
+                "type": "about:blank",
+                "title": "NotFound",
+                "status": 404,
+                "detail": "not found",
+                "instance": format!("urn:uuid:{}", id),
+            })
+        );
+
+        let (status, headers, body) = err.into_problem_response_parts();
+        assert_eq!(status, 404);
+        assert_eq!(
+            headers,
+            [("content-type".to_string(), PROBLEM_JSON.to_string())]
+        );
+        assert_eq!(body["detail"], "not found");
+    }
+}
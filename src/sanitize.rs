@@ -0,0 +1,61 @@
+use crate::{DetailedError, ToResponse};
+
+use std::fmt::{Debug, Display};
+
+/// A public error whose response is adjusted before it's sent, see
+/// [`DetailedError::to_sanitized_response`]
+///
+/// eg. to leave some of the body out depending on the environment, without every
+/// [`ToResponse`] impl having to branch on it. The default impl leaves the response as it is.
+pub trait Sanitize: ToResponse {
+    fn sanitize(&self, _response: &mut Self::Response) {}
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// The same as [`DetailedError::to_response`], passed through the public error's
+    /// [`Sanitize`] impl
+    pub fn to_sanitized_response(&self) -> Pub::Response
+    where
+        Pub: Sanitize,
+    {
+        self.log_if_lazy();
+        let mut response = self.public.to_response();
+        self.public.sanitize(&mut response);
+        response
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use crate::test_support::{capture, io_error, serial, PublicError};
+
+    impl Sanitize for PublicError {
+        fn sanitize(&self, response: &mut Self::Response) {
+            *response = "redacted";
+        }
+    }
+
+    #[test]
+    fn sanitizes_the_response() {
+        let io = io_error();
+        let err: DetailedError<PublicError, &str> = crate::w!(io, PublicError::NotFound, "Users");
+        assert_eq!(err.to_sanitized_response(), "redacted");
+    }
+
+    #[test]
+    fn logs_lazy_errors() {
+        let _serial = serial();
+        let (_, events) = capture(|| {
+            let io = io_error();
+            let err: DetailedError<PublicError, &str> =
+                crate::detailed_error_lazy!(crate::Level::WARN, io, PublicError::NotFound, "Users");
+            err.to_sanitized_response()
+        });
+        assert_eq!(events.len(), 1);
+    }
+}
@@ -0,0 +1,103 @@
+//! Reporting errors to [Sentry](https://sentry.io) through [`sentry_core`], eg. from the `sentry`
+//! crate's client.
+
+use crate::{DetailedError, FieldValue, Level, ToResponse};
+
+use sentry_core::protocol::Value;
+use sentry_core::types::Uuid;
+
+use std::fmt::{Debug, Display};
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Send the private error to Sentry as an event, returning the id of the event (which is nil
+    /// when there's no client bound to the current hub)
+    ///
+    /// The event is built like [`sentry_core::capture_error`] builds it, so each error in the
+    /// chain is an exception. On top of that the category and [`ErrorId`](crate::ErrorId) are
+    /// tags, the level is the equivalent Sentry level and the additional fields are extra data,
+    /// along with the backtrace when the `backtrace` feature has captured one.
+    ///
+    /// Nothing is sent to the client, so this can be used alongside the sanitized response.
+    pub fn capture_sentry(&self) -> Uuid {
+        let mut event = sentry_core::event_from_error(&*self.private);
+        event.level = match self.meta.level {
+            Level::ERROR => sentry_core::Level::Error,
+            Level::WARN => sentry_core::Level::Warning,
+            Level::INFO => sentry_core::Level::Info,
+            _ => sentry_core::Level::Debug,
+        };
+        event
+            .tags
+            .insert("category".to_string(), self.meta.category.to_string());
+        event
+            .tags
+            .insert("error_id".to_string(), self.meta.id.to_string());
+        for (key, value) in &self.meta.fields {
+            event.extra.insert(key.to_string(), to_value(value));
+        }
+        #[cfg(feature = "backtrace")]
+        if let Some(backtrace) = &self.meta.backtrace {
+            event
+                .extra
+                .insert("backtrace".to_string(), backtrace.to_string().into());
+        }
+        sentry_core::capture_event(event)
+    }
+}
+
+fn to_value(value: &FieldValue) -> Value {
+    match value {
+        FieldValue::Str(v) => v.as_str().into(),
+        FieldValue::I64(v) => (*v).into(),
+        FieldValue::U64(v) => (*v).into(),
+        FieldValue::F64(v) => (*v).into(),
+        FieldValue::Bool(v) => (*v).into(),
+        #[cfg(feature = "serde")]
+        FieldValue::Json(v) => v.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{io_error, PublicError};
+    use crate::{DetailedError, Level};
+
+    use sentry_core::protocol::Value;
+    use sentry_core::test::with_captured_events;
+
+    #[test]
+    fn the_event_has_the_details_of_the_error() {
+        let err: DetailedError<PublicError, &str> = DetailedError::new(
+            io_error(),
+            PublicError::NotFound,
+            Some("loading the user"),
+            "Users",
+            Level::WARN,
+            file!(),
+            line!(),
+            module_path!(),
+        )
+        .with_field("user_id", 42);
+        let mut id = None;
+        let events = with_captured_events(|| id = Some(err.capture_sentry()));
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(Some(event.event_id), id);
+        assert_eq!(event.level, sentry_core::Level::Warning);
+        assert_eq!(event.tags["category"], "Users");
+        assert_eq!(event.tags["error_id"], err.error_id().to_string());
+        assert_eq!(event.extra["user_id"], Value::from(42));
+        let messages: Vec<_> = event
+            .exception
+            .iter()
+            .filter_map(|exception| exception.value.as_deref())
+            .collect();
+        assert!(messages.contains(&"loading the user"), "{:?}", messages);
+        assert!(messages.contains(&"connection reset"), "{:?}", messages);
+    }
+}
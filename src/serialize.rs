@@ -0,0 +1,97 @@
+//! Serializing the whole of an error, private side included, eg. for an internal admin or debug
+//! endpoint.
+
+use crate::{DetailedError, ToResponse};
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{Map, Value};
+
+use std::fmt::{Debug, Display};
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// The whole of the error in a form which can be serialized, see [`DebugSerialize`]
+    ///
+    /// This exposes the private error, so it must never be used to build a response for a client,
+    /// that's what [`to_response`](ToResponse::to_response) is for.
+    pub fn debug_serialize(&self) -> DebugSerialize<'_, Pub, Cat> {
+        DebugSerialize(self)
+    }
+}
+
+/// Everything which is emitted when the error is logged: `error_id`, `category`, `level`, `code`,
+/// `file`, `line`, `module`, `fields` and the private error `chain` as its rendered messages,
+/// outermost first
+///
+/// `DetailedError` itself doesn't implement `Serialize`, so that it can't end up in a response
+/// body by accident, this is only created with [`DetailedError::debug_serialize`].
+/// [`WireError`](crate::WireError) is the form to use for sending errors between services.
+pub struct DebugSerialize<'a, Pub, Cat>(&'a DetailedError<Pub, Cat>)
+where
+    Cat: Display,
+    Pub: ToResponse + Debug;
+
+impl<Pub, Cat> Serialize for DebugSerialize<'_, Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let err = self.0;
+        let fields: Map<String, Value> = err
+            .meta
+            .fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), Value::from(value)))
+            .collect();
+        let chain: Vec<String> = err.private.chain().map(|cause| cause.to_string()).collect();
+        let mut state = serializer.serialize_struct("DetailedError", 9)?;
+        state.serialize_field("error_id", &err.meta.id.to_string())?;
+        state.serialize_field("category", &err.meta.category.to_string())?;
+        state.serialize_field("level", err.meta.level.as_str())?;
+        state.serialize_field(
+            "code",
+            &err.error_code().map(|code| code.as_str().to_string()),
+        )?;
+        state.serialize_field("file", &err.meta.file)?;
+        state.serialize_field("line", &err.meta.line)?;
+        state.serialize_field("module", &err.meta.module)?;
+        state.serialize_field("fields", &fields)?;
+        state.serialize_field("chain", &chain)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{io_error, PublicError};
+    use crate::{DetailedError, Level};
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn includes_the_chain_and_fields() {
+        let err: Error = DetailedError::new(
+            io_error(),
+            PublicError::NotFound,
+            Some("loading the user"),
+            "Users",
+            Level::WARN,
+            file!(),
+            line!(),
+            module_path!(),
+        )
+        .with_field("user_id", 7u64);
+        let value = serde_json::to_value(err.debug_serialize()).unwrap();
+
+        assert_eq!(value["category"], "Users");
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(value["fields"]["user_id"], 7);
+        assert_eq!(value["chain"][0], "loading the user");
+        assert_eq!(value["chain"][1], io_error().to_string());
+        assert_eq!(value["error_id"], err.error_id().to_string());
+    }
+}
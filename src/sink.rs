@@ -0,0 +1,187 @@
+//! Sending every logged error to other destinations alongside the `tracing` event, each rendered
+//! in its own format.
+//!
+//! This is useful with differing needs for the same errors, eg. a terse line on the console during
+//! local development while the full details are shipped to an aggregator.
+
+use crate::{config, ErrorId, FieldValue, Level};
+
+use std::fmt::Write;
+use std::sync::{Arc, RwLock};
+
+/// How much of a [`LogRecord`] a sink receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SinkFormat {
+    /// Just the level, category and message, eg. `WARN NotFound: no user with id 42`
+    Compact,
+    /// Everything in the record, as space separated `key=value` pairs
+    Full,
+    /// Everything in the record, as a JSON object
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+/// What's shared with every sink when an error is logged, each sink renders it according to its
+/// [`SinkFormat`]
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub level: Level,
+    pub error_id: ErrorId,
+    pub category: String,
+    pub public_error: String,
+    pub errors: Vec<String>,
+    pub fields: Vec<(String, FieldValue)>,
+    pub file: String,
+    pub line: u32,
+    pub module: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    /// The keys of the [full](SinkFormat::Full) and [JSON](SinkFormat::Json) formats follow the
+    /// [key casing](crate::set_key_case) of the emitted events
+    pub fn render(&self, format: SinkFormat) -> String {
+        let case = config::key_case();
+        let key = |key: &'static str| case.apply(key);
+        match format {
+            SinkFormat::Compact => format!("{} {}: {}", self.level, self.category, self.message),
+            SinkFormat::Full => {
+                let mut rendered = format!(
+                    "{}={} {}={} {}={:?} {}={:?} {}={:?}",
+                    key("level"),
+                    self.level,
+                    key("error_id"),
+                    self.error_id,
+                    key("category"),
+                    self.category,
+                    key("public_error"),
+                    self.public_error,
+                    key("errors"),
+                    self.errors
+                );
+                for (name, value) in &self.fields {
+                    let name = case.apply(name);
+                    let _ = match value {
+                        FieldValue::Str(v) => write!(rendered, " {}={:?}", name, v),
+                        v => write!(rendered, " {}={}", name, v),
+                    };
+                }
+                let _ = write!(
+                    rendered,
+                    " {}={:?} {}={} {}={:?} message={:?}",
+                    key("file"),
+                    self.file,
+                    key("line"),
+                    self.line,
+                    key("module"),
+                    self.module,
+                    self.message
+                );
+                rendered
+            }
+            #[cfg(feature = "serde")]
+            SinkFormat::Json => {
+                use serde_json::{Map, Value};
+
+                let fields: Map<String, Value> = self
+                    .fields
+                    .iter()
+                    .map(|(name, value)| (case.apply(name).into_owned(), Value::from(value)))
+                    .collect();
+                let mut record = Map::new();
+                let mut insert = |name: &'static str, value: Value| {
+                    record.insert(key(name).into_owned(), value);
+                };
+                insert("level", self.level.as_str().into());
+                insert("error_id", self.error_id.to_string().into());
+                insert("category", self.category.as_str().into());
+                insert("public_error", self.public_error.as_str().into());
+                insert("errors", self.errors.clone().into());
+                insert("fields", fields.into());
+                insert("file", self.file.as_str().into());
+                insert("line", self.line.into());
+                insert("module", self.module.as_str().into());
+                record.insert("message".to_string(), self.message.as_str().into());
+                Value::Object(record).to_string()
+            }
+        }
+    }
+}
+
+type Sink = (SinkFormat, Arc<dyn Fn(String) + Send + Sync>);
+
+static SINKS: RwLock<Vec<Sink>> = RwLock::new(Vec::new());
+
+/// Send every error which is logged to `sink` as well, rendered in `format`
+pub fn add_sink<F>(format: SinkFormat, sink: F)
+where
+    F: Fn(String) + Send + Sync + 'static,
+{
+    SINKS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((format, Arc::new(sink)));
+}
+
+/// Remove all of the sinks added through [`add_sink`]
+pub fn clear_sinks() {
+    SINKS.write().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Send a record to every sink, it's only built if there are any
+pub(crate) fn dispatch(record: impl FnOnce() -> LogRecord) {
+    let sinks = SINKS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if sinks.is_empty() {
+        return;
+    }
+    let record = record();
+    for (format, sink) in sinks {
+        sink(record.render(format));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    use std::sync::Mutex;
+
+    type Rendered = Arc<Mutex<Vec<String>>>;
+
+    /// A sink which keeps what it's sent, the category is used to tell these errors apart from any
+    /// logged by the other tests
+    fn keep(format: SinkFormat) -> Rendered {
+        let rendered = Rendered::default();
+        let kept = Arc::clone(&rendered);
+        add_sink(format, move |line| {
+            if line.contains("Sinks") {
+                kept.lock().unwrap().push(line);
+            }
+        });
+        rendered
+    }
+
+    #[test]
+    fn each_sink_gets_its_own_format() {
+        let _serial = serial();
+        let compact = keep(SinkFormat::Compact);
+        let full = keep(SinkFormat::Full);
+        let io = io_error();
+        let err: DetailedError<PublicError, &str> =
+            crate::w!(io, PublicError::NotFound, "Sinks", "loading", "user_id" => 42);
+        clear_sinks();
+
+        assert_eq!(*compact.lock().unwrap(), ["WARN Sinks: loading"]);
+        let full = full.lock().unwrap();
+        assert_eq!(full.len(), 1);
+        assert!(full[0].starts_with(&format!(
+            "level=WARN error_id={} category=\"Sinks\" public_error=\"NotFound\"",
+            err.error_id()
+        )));
+        assert!(full[0].contains(" user_id=42 "));
+        assert!(full[0].ends_with(" message=\"loading\""));
+    }
+}
@@ -0,0 +1,42 @@
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+
+/// A copy of an error chain which only keeps the rendered message of each cause, used where the
+/// private error needs to be duplicated as reports can't be cloned
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    message: String,
+    source: Option<Box<Snapshot>>,
+}
+
+impl Snapshot {
+    pub(crate) fn new<'a>(
+        chain: impl DoubleEndedIterator<Item = &'a (dyn StdError + 'static)>,
+    ) -> Self {
+        let mut snapshot: Option<Snapshot> = None;
+        for cause in chain.rev() {
+            snapshot = Some(Snapshot {
+                message: cause.to_string(),
+                source: snapshot.map(Box::new),
+            });
+        }
+        snapshot.unwrap_or_else(|| Snapshot {
+            message: String::new(),
+            source: None,
+        })
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl StdError for Snapshot {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|s| s as &(dyn StdError + 'static))
+    }
+}
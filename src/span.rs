@@ -0,0 +1,74 @@
+use crate::{DetailedError, ToResponse};
+
+use tracing::field::display;
+use tracing::Span;
+
+use std::fmt::{Debug, Display};
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    /// Record the error onto its [span](Self::with_span), or the current one, rather than
+    /// emitting an event for it, eg. where each request is a single span which is exported once it
+    /// closes
+    ///
+    /// The private error is recorded as `error`, along with its `category` and `error_id`, and each
+    /// of the additional fields under its key. A span only records the fields it was created with,
+    /// anything else is silently dropped, so the fields have to be declared up front (see
+    /// [`error_span!`](crate::error_span)). None of the formatting or configuration applied to the
+    /// emitted event (eg. the [key case](crate::set_key_case)) is applied here.
+    ///
+    /// The error is considered to have been logged afterwards, this has no effect if it already
+    /// has been. As the macros log the error straight away, this is for errors which are
+    /// [opened](Self::open) or built with [`to_span`](crate::DetailedErrorBuilder::to_span).
+    pub fn log_to_span(&mut self) {
+        if self.meta.has_logged.get() {
+            return;
+        }
+        let span = self.meta.span.clone().unwrap_or_else(Span::current);
+        span.record("error", display(&self.private));
+        span.record("category", display(&self.meta.category));
+        span.record("error_id", display(&self.meta.id));
+        for (key, value) in &self.meta.fields {
+            span.record(key.as_ref(), value.recorded().as_value());
+        }
+        self.meta.has_logged.set(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{capture_all, io_error, serial, PublicError};
+    use crate::DetailedError;
+
+    #[test]
+    fn the_error_is_recorded_onto_the_current_span() {
+        let _serial = serial();
+        let (err, events, records) = capture_all(|| {
+            let span = crate::error_span!(tracing::Level::INFO, "request", user_id);
+            let _entered = span.enter();
+            let mut err: DetailedError<PublicError, &str> =
+                DetailedError::open(io_error(), PublicError::NotFound, "Users")
+                    .context("loading the user")
+                    .with_field("user_id", 42)
+                    .with_field("undeclared", 1);
+            err.log_to_span();
+            err
+        });
+
+        assert!(err.is_logged());
+        assert!(events.iter().all(|event| !event.error));
+        let recorded: std::collections::BTreeMap<_, _> = records
+            .iter()
+            .filter(|record| record.span == "request")
+            .flat_map(|record| record.fields.clone())
+            .collect();
+        assert_eq!(recorded["error"], "loading the user");
+        assert_eq!(recorded["category"], "Users");
+        assert_eq!(recorded["error_id"], err.error_id().to_string());
+        assert_eq!(recorded["user_id"], "42");
+        assert!(!recorded.contains_key("undeclared"));
+    }
+}
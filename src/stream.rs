@@ -0,0 +1,181 @@
+use crate::{DetailedError, ToResponse};
+
+use bytes::Bytes;
+use futures_core::Stream;
+
+use std::fmt::{Debug, Display};
+use std::future::poll_fn;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+
+/// Drive a stream of results to completion, separating the successes from the errors
+///
+/// Each error is logged as it's received (if it hasn't been already), so that it's possible to
+/// carry on processing the rest of the stream and decide how to respond at the end.
+pub async fn collect_errors<S, T, Pub, Cat>(stream: S) -> (Vec<T>, Vec<DetailedError<Pub, Cat>>)
+where
+    S: Stream<Item = Result<T, DetailedError<Pub, Cat>>>,
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    let mut stream = pin!(stream);
+    let mut oks = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(result) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        match result {
+            Ok(t) => oks.push(t),
+            Err(mut e) => {
+                e.log();
+                errors.push(e);
+            }
+        }
+    }
+    (oks, errors)
+}
+
+/// A public error whose response body can be produced incrementally, for errors which carry large
+/// payloads (eg. a big validation report) that'd be wasteful to assemble in memory
+///
+/// Bodies which are small enough to build in one go can use [`buffered_stream`]:
+///
+/// ```ignore
+/// impl ToResponseStream for PublicError {
+///     fn to_response_stream(&self) -> impl Stream<Item = Bytes> + Send + 'static + use<> {
+///         api_error::buffered_stream(self.to_response())
+///     }
+/// }
+/// ```
+///
+/// The stream can't borrow from the public error (hence the `use<>`) so that it can outlive it,
+/// eg. as the body of an axum response (see `DetailedError::into_stream_response`). Anything it
+/// needs should be shared or cloned into it.
+pub trait ToResponseStream: ToResponse {
+    fn to_response_stream(&self) -> impl Stream<Item = Bytes> + Send + 'static + use<Self>;
+}
+
+/// A stream which yields the whole of `body` as a single chunk
+pub fn buffered_stream(body: impl Into<Bytes>) -> impl Stream<Item = Bytes> + Send {
+    Buffered(Some(body.into()))
+}
+
+struct Buffered(Option<Bytes>);
+
+impl Stream for Buffered {
+    type Item = Bytes;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.take())
+    }
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponseStream + Debug,
+{
+    /// See [`ToResponseStream`]
+    pub fn to_response_stream(&self) -> impl Stream<Item = Bytes> + Send + 'static + use<Pub, Cat> {
+        self.log_if_lazy();
+        self.public.to_response_stream()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{buffered_stream, collect_errors, ToResponseStream};
+    use crate::test_support::{io_error, PublicError};
+    use crate::{DetailedError, ToResponse};
+
+    use bytes::Bytes;
+    use futures_core::Stream;
+
+    use std::future::poll_fn;
+    use std::pin::{pin, Pin};
+    use std::task::{Context, Poll};
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    struct Iter<I>(I);
+
+    impl<I: Iterator + Unpin> Stream for Iter<I> {
+        type Item = I::Item;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.0.next())
+        }
+    }
+
+    fn opened(item: u32) -> Error {
+        DetailedError::open(io_error(), PublicError::NotFound, "Items").with_field("item", item)
+    }
+
+    #[test]
+    fn separates_the_successes_from_the_errors() {
+        let results: Vec<Result<u32, Error>> = vec![Ok(1), Err(opened(2)), Ok(3), Err(opened(4))];
+        let (oks, errors) = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(collect_errors(Iter(results.into_iter())));
+        assert_eq!(oks, [1, 3]);
+        let items: Vec<_> = errors
+            .iter()
+            .map(|err| err.fields()["item"].clone())
+            .collect();
+        assert_eq!(items, [2u32.into(), 4u32.into()]);
+        assert!(errors.iter().all(Error::is_logged));
+    }
+
+    /// A report which is streamed an entry at a time
+    #[derive(Debug)]
+    struct Report(Vec<&'static str>);
+
+    impl ToResponse for Report {
+        type Response = String;
+
+        fn to_response(&self) -> Self::Response {
+            self.0.concat()
+        }
+    }
+
+    impl ToResponseStream for Report {
+        fn to_response_stream(&self) -> impl Stream<Item = Bytes> + Send + 'static + use<> {
+            Iter(
+                self.0
+                    .clone()
+                    .into_iter()
+                    .map(|entry| Bytes::from_static(entry.as_bytes())),
+            )
+        }
+    }
+
+    impl ToResponseStream for PublicError {
+        fn to_response_stream(&self) -> impl Stream<Item = Bytes> + Send + 'static + use<> {
+            buffered_stream(self.to_response())
+        }
+    }
+
+    fn collect(stream: impl Stream<Item = Bytes>) -> (usize, Vec<u8>) {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let mut stream = pin!(stream);
+            let (mut chunks, mut body) = (0, Vec::new());
+            while let Some(chunk) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+                chunks += 1;
+                body.extend_from_slice(&chunk);
+            }
+            (chunks, body)
+        })
+    }
+
+    #[test]
+    fn the_stream_matches_the_buffered_body() {
+        let report: DetailedError<Report, &str> =
+            DetailedError::open(io_error(), Report(vec!["{\"a\":1,", "\"b\":2}"]), "Items");
+        let (chunks, body) = collect(report.to_response_stream());
+        assert_eq!(chunks, 2);
+        assert_eq!(body, report.to_response().as_bytes());
+
+        let buffered: Error = opened(1);
+        let (chunks, body) = collect(buffered.to_response_stream());
+        assert_eq!(chunks, 1);
+        assert_eq!(body, buffered.to_response().as_bytes());
+    }
+}
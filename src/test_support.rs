@@ -0,0 +1,235 @@
+//! What the unit tests share: a public error to build errors with, and capturing the events which
+//! are emitted.
+
+use crate::ToResponse;
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PublicError {
+    NotFound,
+}
+
+impl ToResponse for PublicError {
+    type Response = &'static str;
+
+    fn to_response(&self) -> Self::Response {
+        match self {
+            PublicError::NotFound => "not found",
+        }
+    }
+
+    fn status_code(&self) -> u16 {
+        match self {
+            PublicError::NotFound => 404,
+        }
+    }
+}
+
+impl fmt::Display for PublicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PublicError::NotFound => f.write_str("not found"),
+        }
+    }
+}
+
+pub(crate) fn io_error() -> std::io::Error {
+    std::io::Error::other("connection reset")
+}
+
+/// The configuration is global, so the tests which change it or emit events run one at a time
+pub(crate) fn serial() -> MutexGuard<'static, ()> {
+    static SERIAL: Mutex<()> = Mutex::new(());
+    SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) use capture::{capture, capture_all};
+
+#[cfg(feature = "tracing")]
+mod capture {
+    use super::*;
+
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Registry;
+
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+    use std::sync::Arc;
+
+    /// An event which was emitted, with each of its fields rendered
+    #[derive(Debug, Clone)]
+    pub(crate) struct Captured {
+        pub(crate) level: tracing::Level,
+        pub(crate) target: String,
+        pub(crate) module: Option<String>,
+        /// The name of the span it was emitted within
+        pub(crate) span: Option<String>,
+        pub(crate) fields: BTreeMap<String, String>,
+        /// How each of the fields was recorded, eg. `i64` or `str`
+        pub(crate) kinds: BTreeMap<String, &'static str>,
+        /// Whether it was emitted by [`DetailedError::log`](crate::DetailedError::log)
+        pub(crate) error: bool,
+    }
+
+    impl Captured {
+        pub(crate) fn field(&self, name: &str) -> Option<&str> {
+            self.fields.get(name).map(String::as_str)
+        }
+
+        pub(crate) fn kind(&self, name: &str) -> Option<&'static str> {
+            self.kinds.get(name).copied()
+        }
+    }
+
+    /// The values recorded onto a span after it was created, eg. by
+    /// [`DetailedError::log_to_span`](crate::DetailedError::log_to_span)
+    #[derive(Debug, Clone)]
+    pub(crate) struct Recorded {
+        pub(crate) span: String,
+        pub(crate) fields: BTreeMap<String, String>,
+    }
+
+    #[derive(Clone, Default)]
+    struct Capture {
+        events: Arc<Mutex<Vec<Captured>>>,
+        records: Arc<Mutex<Vec<Recorded>>>,
+    }
+
+    impl<S> Layer<S> for Capture
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let metadata = event.metadata();
+            let mut render = Render::default();
+            event.record(&mut render);
+            self.events
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(Captured {
+                    level: *metadata.level(),
+                    target: metadata.target().to_string(),
+                    module: metadata.module_path().map(str::to_string),
+                    span: ctx.event_span(event).map(|span| span.name().to_string()),
+                    fields: render.fields,
+                    kinds: render.kinds,
+                    error: crate::emit::is_error_event(metadata),
+                });
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: Context<'_, S>,
+        ) {
+            let Some(span) = ctx.span(id) else {
+                return;
+            };
+            let mut render = Render::default();
+            values.record(&mut render);
+            self.records
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(Recorded {
+                    span: span.name().to_string(),
+                    fields: render.fields,
+                });
+        }
+    }
+
+    #[derive(Default)]
+    struct Render {
+        fields: BTreeMap<String, String>,
+        kinds: BTreeMap<String, &'static str>,
+    }
+
+    impl Render {
+        fn insert(&mut self, field: &Field, kind: &'static str, value: String) {
+            self.fields.insert(field.name().to_string(), value);
+            self.kinds.insert(field.name().to_string(), kind);
+        }
+    }
+
+    impl Visit for Render {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.insert(field, "debug", format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.insert(field, "str", value.to_string());
+        }
+
+        fn record_i64(&mut self, field: &Field, value: i64) {
+            self.insert(field, "i64", value.to_string());
+        }
+
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            self.insert(field, "u64", value.to_string());
+        }
+
+        fn record_f64(&mut self, field: &Field, value: f64) {
+            self.insert(field, "f64", value.to_string());
+        }
+
+        fn record_bool(&mut self, field: &Field, value: bool) {
+            self.insert(field, "bool", value.to_string());
+        }
+
+        /// The named fields of a structured value are also rendered as `field.name`
+        #[cfg(feature = "valuable")]
+        fn record_value(&mut self, field: &Field, value: valuable::Value<'_>) {
+            self.insert(field, "value", format!("{:?}", value));
+            if let valuable::Value::Structable(structable) = value {
+                structable.visit(&mut Flatten(field.name(), self));
+            }
+        }
+    }
+
+    #[cfg(feature = "valuable")]
+    struct Flatten<'a>(&'a str, &'a mut Render);
+
+    #[cfg(feature = "valuable")]
+    impl valuable::Visit for Flatten<'_> {
+        fn visit_value(&mut self, _value: valuable::Value<'_>) {}
+
+        fn visit_named_fields(&mut self, named: &valuable::NamedValues<'_>) {
+            for (field, value) in named {
+                let name = format!("{}.{}", self.0, field.name());
+                self.1.fields.insert(name.clone(), format!("{:?}", value));
+                self.1.kinds.insert(name, "value");
+            }
+        }
+    }
+
+    /// Run `f` and return the events it emitted on the current thread, the caller has to hold
+    /// [`serial`](super::serial)
+    pub(crate) fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Captured>) {
+        let (out, events, _) = capture_all(f);
+        (out, events)
+    }
+
+    /// The same as [`capture`], along with the values recorded onto spans
+    pub(crate) fn capture_all<T>(f: impl FnOnce() -> T) -> (T, Vec<Captured>, Vec<Recorded>) {
+        let capture = Capture::default();
+        let subscriber = Registry::default().with(capture.clone());
+        let out = tracing::subscriber::with_default(subscriber, f);
+        let events = capture
+            .events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let records = capture
+            .records
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        (out, events, records)
+    }
+}
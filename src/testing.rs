@@ -0,0 +1,111 @@
+//! Helpers for asserting on the errors logged by your code in tests.
+
+use crate::emit;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+use std::fmt::{Debug, Write};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct Capture {
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl<S: Subscriber> Layer<S> for Capture {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if !emit::is_error_event(metadata) {
+            return;
+        }
+        let mut rendered = format!("{} {}:", metadata.level(), metadata.target());
+        event.record(&mut Render(&mut rendered));
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(rendered);
+    }
+}
+
+struct Render<'a>(&'a mut String);
+
+impl Visit for Render<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        let _ = write!(self.0, " {}={:?}", field.name(), value);
+    }
+}
+
+/// Run `f` and panic if any [`DetailedError`](crate::DetailedError) was logged while it ran,
+/// printing the events which were, eg. to make sure a happy path doesn't log errors along the way
+///
+/// Only the events emitted on the current thread whilst `f` runs are counted, errors logged on
+/// other threads (including by tasks spawned onto a multi-threaded runtime) aren't seen. The
+/// events are captured by a subscriber which replaces any default one for the duration of `f`,
+/// so they aren't output anywhere else. Only the events emitted by
+/// [`DetailedError::log`](crate::DetailedError::log) are counted, not eg. summaries of batches or
+/// recurring errors.
+#[track_caller]
+pub fn assert_no_errors<T>(f: impl FnOnce() -> T) -> T {
+    let capture = Capture::default();
+    let subscriber = Registry::default().with(capture.clone());
+    let out = tracing::subscriber::with_default(subscriber, f);
+
+    let events = capture.events.lock().unwrap_or_else(|e| e.into_inner());
+    if !events.is_empty() {
+        panic!(
+            "expected no errors to be logged, found {}:\n{}",
+            events.len(),
+            events.join("\n")
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, serial, PublicError};
+    use crate::{set_key_case, DetailedError, KeyCase};
+
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn lifecycle_events_are_not_errors() {
+        let _serial = serial();
+        assert_no_errors(|| {
+            let err: Error = DetailedError::open(io_error(), PublicError::NotFound, "Users");
+            err.resolve(Ok::<_, String>(())).unwrap();
+        });
+    }
+
+    #[test]
+    fn logged_errors_are_found() {
+        let _serial = serial();
+        let found = catch_unwind(|| {
+            assert_no_errors(|| {
+                let io = io_error();
+                let _err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            })
+        });
+        assert!(found.is_err());
+    }
+
+    #[test]
+    fn logged_errors_are_found_with_any_key_case() {
+        let _serial = serial();
+        set_key_case(KeyCase::Camel);
+        let found = catch_unwind(AssertUnwindSafe(|| {
+            assert_no_errors(|| {
+                let io = io_error();
+                let _err: Error = crate::w!(io, PublicError::NotFound, "Users");
+            })
+        }));
+        set_key_case(KeyCase::Snake);
+        assert!(found.is_err());
+    }
+}
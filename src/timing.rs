@@ -0,0 +1,92 @@
+use crate::{config, DetailedError, InnerError, ToResponse};
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display};
+use std::panic::Location;
+use std::time::Instant;
+
+/// Run `f`, turning its error into one with `category` and `public` along with how long it took
+/// to fail (see [`DetailedError::with_elapsed`]), so that slow failures stand out from fast ones
+///
+/// The error is logged straight away at the [default level](crate::set_default_level).
+#[track_caller]
+pub fn time_operation<T, E, Pub, Cat, F>(
+    category: Cat,
+    public: Pub,
+    f: F,
+) -> Result<T, DetailedError<Pub, Cat>>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+    E: StdError + Send + Sync + 'static,
+    F: FnOnce() -> Result<T, E>,
+{
+    let location = Location::caller();
+    let start = Instant::now();
+    f().map_err(|private| {
+        let elapsed = start.elapsed();
+        let mut err = DetailedError::from_inner(
+            InnerError::new(private),
+            Some(std::any::type_name::<E>()),
+            public,
+            category,
+            config::default_level(),
+            location.file(),
+            location.line(),
+            "",
+            HashMap::with_capacity(0),
+        )
+        .with_elapsed(elapsed);
+        err.log();
+        err
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{io_error, serial, PublicError};
+
+    use std::time::Duration;
+
+    type Error = DetailedError<PublicError, &'static str>;
+
+    #[test]
+    fn failures_are_timed() {
+        let _serial = serial();
+        crate::set_default_level(crate::Level::WARN);
+        let result: Result<(), Error> = time_operation("Users", PublicError::NotFound, || {
+            std::thread::sleep(Duration::from_millis(5));
+            Err(io_error())
+        });
+        crate::set_default_level(crate::Level::ERROR);
+
+        let err = result.unwrap_err();
+        assert!(err.elapsed().unwrap() >= Duration::from_millis(5));
+        assert_eq!(err.level(), crate::Level::WARN);
+        assert_eq!(err.category(), &"Users");
+        assert!(err.is_logged());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_elapsed_time_is_emitted() {
+        let _serial = serial();
+        let (_, events) = crate::test_support::capture(|| {
+            let _result: Result<(), Error> =
+                time_operation("Users", PublicError::NotFound, || Err(io_error()));
+        });
+        let event = events.iter().find(|event| event.error).unwrap();
+        assert!(event.field("elapsed_ms").unwrap().parse::<u64>().is_ok());
+        assert_eq!(event.level, crate::Level::ERROR);
+    }
+
+    #[test]
+    fn values_are_returned_as_is() {
+        let result: Result<_, Error> = time_operation("Users", PublicError::NotFound, || {
+            Ok::<_, std::io::Error>(7)
+        });
+        assert_eq!(result.unwrap(), 7);
+    }
+}
@@ -0,0 +1,56 @@
+//! Support for rejecting requests with errors in [`warp`](https://docs.rs/warp) filters
+
+use crate::{DetailedError, ToResponse};
+
+use serde_json::Value;
+use warp::http::header::{HeaderName, HeaderValue};
+use warp::http::StatusCode;
+use warp::reject::{Reject, Rejection};
+use warp::reply::{self, Reply, Response};
+
+use std::fmt::{Debug, Display};
+
+/// Allows the error to be returned through [`warp::reject::custom`] and then turned into a response
+/// by [`recover_rejection`]
+impl<Pub, Cat> Reject for DetailedError<Pub, Cat>
+where
+    Cat: Display + Send + Sync + 'static,
+    Pub: ToResponse + Debug + Send + Sync + 'static,
+{
+}
+
+/// Turn a rejection holding a [`DetailedError`] into a response, for use with
+/// [`Filter::recover`](warp::Filter::recover)
+///
+/// ```ignore
+/// let routes = route.recover(api_error::recover_rejection::<PublicError, Category>);
+/// ```
+///
+/// The response is the [JSON response](DetailedError::into_json_response_parts) of the error, the
+/// same as with the other integrations, anything else is rejected again so that it can be handled
+/// further up.
+///
+/// Warp only lends out what a rejection holds so the error can't be taken back, nor can it be
+/// logged at this point. As errors are logged when they're created this only matters for those
+/// which haven't been (eg. [opened](DetailedError::open) ones), which should be
+/// [logged](DetailedError::log) before they're rejected. The `Pub` and `Cat` have to be exactly
+/// those of the error, a rejection holding a `DetailedError` of other types is passed on.
+pub async fn recover_rejection<Pub, Cat>(rejection: Rejection) -> Result<Response, Rejection>
+where
+    Cat: Display + Send + Sync + 'static,
+    Pub: ToResponse + Debug + Send + Sync + 'static,
+    Pub::Response: Into<Value>,
+{
+    let Some(err) = rejection.find::<DetailedError<Pub, Cat>>() else {
+        return Err(rejection);
+    };
+    let (status, headers, body) = err.json_response_parts();
+    let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut response = reply::with_status(reply::json(&body), status).into_response();
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    Ok(response)
+}
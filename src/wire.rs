@@ -0,0 +1,263 @@
+//! Sending errors across a service boundary, eg. from a worker back to the coordinator over a
+//! queue.
+//!
+//! The private error can't be deserialized, so its chain is sent as the rendered messages and the
+//! receiving side gets a read-only [`ReconstructedError`] rather than a [`DetailedError`]. That
+//! can be turned back into a [`DetailedError`] with [`DetailedError::from_reconstructed`] to log
+//! it under the original error id.
+
+use crate::{config, DetailedError, ErrorId, FieldValue, InnerError, Level, ToResponse};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::panic::Location;
+
+/// The serializable form of a [`DetailedError`], see [`DetailedError::to_wire`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WireError {
+    pub error_id: String,
+    pub category: String,
+    pub code: Option<String>,
+    pub level: String,
+    pub status: u16,
+    pub body: Value,
+    pub file: String,
+    pub line: u32,
+    pub module: String,
+    pub fields: BTreeMap<String, Value>,
+    /// The messages of the private error chain, outermost first
+    pub causes: Vec<String>,
+}
+
+impl<Pub, Cat> DetailedError<Pub, Cat>
+where
+    Cat: Display,
+    Pub: ToResponse + Debug,
+{
+    pub fn to_wire(&self) -> WireError
+    where
+        Pub::Response: Into<Value>,
+    {
+        WireError {
+            error_id: self.meta.id.to_string(),
+            category: self.meta.category.to_string(),
+            code: self.error_code().map(|code| code.as_str().to_string()),
+            level: self.meta.level.to_string(),
+            status: self.status_code(),
+            body: self.to_response().into(),
+            file: self.meta.file.to_string(),
+            line: self.meta.line,
+            module: self.meta.module.to_string(),
+            fields: self
+                .meta
+                .fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), Value::from(value)))
+                .collect(),
+            causes: self
+                .private
+                .chain()
+                .map(|cause| cause.to_string())
+                .collect(),
+        }
+    }
+
+    /// Rebuild an error which was received as a [`WireError`] on this side of the boundary, with
+    /// the [`ReconstructedError`] as its private error
+    ///
+    /// It keeps the error id, level and fields of the original, so that the event emitted here can
+    /// be matched to the one emitted by the sender. The error is logged straight away, and its
+    /// location is where this is called.
+    #[track_caller]
+    pub fn from_reconstructed(
+        reconstructed: ReconstructedError,
+        public: Pub,
+        category: Cat,
+    ) -> Self {
+        let location = Location::caller();
+        let level = reconstructed.level().unwrap_or_else(config::default_level);
+        let id = reconstructed.error_id();
+        let fields: HashMap<Cow<'static, str>, FieldValue> = reconstructed
+            .fields()
+            .iter()
+            .map(|(key, value)| (Cow::Owned(key.clone()), FieldValue::from(value.clone())))
+            .collect();
+        let mut err = DetailedError::from_inner(
+            InnerError::new(reconstructed),
+            Some(std::any::type_name::<ReconstructedError>()),
+            public,
+            category,
+            level,
+            location.file(),
+            location.line(),
+            "",
+            fields,
+        );
+        if let Some(id) = id {
+            err.meta.id = id;
+        }
+        err.log();
+        err
+    }
+}
+
+/// A read-only view of an error received as a [`WireError`]
+///
+/// It displays as the outermost message of the chain, the whole of which is available through
+/// [`causes`](Self::causes).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconstructedError {
+    wire: WireError,
+}
+
+impl ReconstructedError {
+    pub fn from_wire(wire: WireError) -> Self {
+        ReconstructedError { wire }
+    }
+
+    /// The id of the original error, this is `None` if it isn't a valid UUID
+    pub fn error_id(&self) -> Option<ErrorId> {
+        ErrorId::parse(&self.wire.error_id)
+    }
+
+    pub fn category(&self) -> &str {
+        &self.wire.category
+    }
+
+    pub fn error_code(&self) -> Option<&str> {
+        self.wire.code.as_deref()
+    }
+
+    /// The level the original error was logged at, this is `None` if it isn't a valid level
+    pub fn level(&self) -> Option<Level> {
+        self.wire.level.parse().ok()
+    }
+
+    pub fn status_code(&self) -> u16 {
+        self.wire.status
+    }
+
+    pub fn body(&self) -> &Value {
+        &self.wire.body
+    }
+
+    /// The file and line the original error was created at
+    pub fn location(&self) -> (&str, u32) {
+        (&self.wire.file, self.wire.line)
+    }
+
+    pub fn module(&self) -> &str {
+        &self.wire.module
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, Value> {
+        &self.wire.fields
+    }
+
+    pub fn causes(&self) -> &[String] {
+        &self.wire.causes
+    }
+
+    pub fn into_wire(self) -> WireError {
+        self.wire
+    }
+}
+
+impl From<WireError> for ReconstructedError {
+    fn from(wire: WireError) -> Self {
+        ReconstructedError::from_wire(wire)
+    }
+}
+
+impl Display for ReconstructedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.wire.causes.first() {
+            Some(cause) => write!(f, "{}", cause),
+            None => write!(f, "{}", self.wire.category),
+        }
+    }
+}
+
+impl StdError for ReconstructedError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::io_error;
+
+    use serde_json::json;
+
+    #[derive(Debug)]
+    struct NotFound;
+
+    impl ToResponse for NotFound {
+        type Response = Value;
+
+        fn to_response(&self) -> Self::Response {
+            json!({ "msg": "not found" })
+        }
+
+        fn status_code(&self) -> u16 {
+            404
+        }
+
+        fn error_code(&self) -> Option<crate::ErrorCode> {
+            crate::ErrorCode::new("USER_NOT_FOUND").ok()
+        }
+    }
+
+    fn sent() -> DetailedError<NotFound, &'static str> {
+        DetailedError::open(io_error(), NotFound, "Users")
+            .context("loading the user")
+            .with_field("user_id", 42)
+            .with_field("cached", true)
+    }
+
+    #[test]
+    fn every_field_survives_the_round_trip() {
+        let err = sent();
+        let json = serde_json::to_string(&err.to_wire()).unwrap();
+        let received = ReconstructedError::from_wire(serde_json::from_str(&json).unwrap());
+
+        assert_eq!(received.error_id(), Some(err.error_id()));
+        assert_eq!(received.category(), "Users");
+        assert_eq!(received.error_code(), Some("USER_NOT_FOUND"));
+        assert_eq!(received.level(), Some(err.level()));
+        assert_eq!(received.status_code(), 404);
+        assert_eq!(received.body(), &json!({ "msg": "not found" }));
+        assert_eq!(received.location(), (err.file(), err.line()));
+        assert_eq!(received.module(), err.module());
+        assert_eq!(received.fields()["user_id"], json!(42));
+        assert_eq!(received.fields()["cached"], json!(true));
+        assert_eq!(received.causes(), ["loading the user", "connection reset"]);
+        assert_eq!(received.to_string(), "loading the user");
+        assert_eq!(received.clone().into_wire(), err.to_wire());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn the_rebuilt_error_logs_with_the_original_id() {
+        use crate::test_support::{capture, serial};
+
+        let _serial = serial();
+        let err = sent();
+        let json = serde_json::to_string(&err.to_wire()).unwrap();
+        let received = ReconstructedError::from_wire(serde_json::from_str(&json).unwrap());
+        let (rebuilt, events) =
+            capture(|| DetailedError::from_reconstructed(received, NotFound, "Coordinator"));
+
+        assert_eq!(rebuilt.error_id(), err.error_id());
+        let event = events.iter().find(|event| event.error).unwrap();
+        assert_eq!(
+            event.field("error_id"),
+            Some(err.error_id().to_string().as_str())
+        );
+        assert_eq!(event.field("user_id"), Some("42"));
+        assert_eq!(event.field("message"), Some("loading the user"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Only built with the `actix-web` feature, eg. `cargo test --features actix-web --test actix`
+#![cfg(all(feature = "actix-web", feature = "tracing"))]
+
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use api_error::{e, DetailedError, ToResponse};
+use serde_json::{json, Value};
+use thiserror::Error as ThisError;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, ThisError)]
+enum PublicError {
+    #[error("The user couldn't be found")]
+    UserNotFound,
+    #[error("Too many requests, please try again in 5 seconds")]
+    RateLimited,
+}
+
+impl ToResponse for PublicError {
+    type Response = Value;
+
+    fn to_response(&self) -> Self::Response {
+        json!({ "msg": self.to_string() })
+    }
+
+    fn status_code(&self) -> u16 {
+        match self {
+            PublicError::UserNotFound => 404,
+            PublicError::RateLimited => 429,
+        }
+    }
+
+    fn headers(&self) -> Vec<(String, String)> {
+        match self {
+            PublicError::UserNotFound => Vec::new(),
+            PublicError::RateLimited => vec![("Retry-After".to_string(), "5".to_string())],
+        }
+    }
+}
+
+type Error = DetailedError<PublicError, &'static str>;
+
+async fn load_user() -> Result<&'static str, Error> {
+    let err = std::io::Error::other("no user 42");
+    Err(e!(
+        err,
+        PublicError::UserNotFound,
+        "Users",
+        "loading the user"
+    ))
+}
+
+async fn list_users() -> Result<&'static str, Error> {
+    let err = std::io::Error::other("rate limit exceeded");
+    Err(e!(
+        err,
+        PublicError::RateLimited,
+        "Users",
+        "listing the users"
+    ))
+}
+
+/// Counts the events emitted by the crate
+#[derive(Clone, Default)]
+struct Count(Arc<AtomicUsize>);
+
+impl<S: Subscriber> Layer<S> for Count {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if event.metadata().target() == "api_error" {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn responds_with_the_error_and_logs_it_once() {
+    let count = Count::default();
+    // The test runs on a single threaded runtime, so everything is emitted on this thread
+    let _guard = tracing::subscriber::set_default(Registry::default().with(count.clone()));
+
+    let app = test::init_service(App::new().route("/users/42", web::get().to(load_user))).await;
+    let request = test::TestRequest::get().uri("/users/42").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: Value = test::read_body_json(response).await;
+    assert_eq!(body, json!({ "msg": "The user couldn't be found" }));
+    assert_eq!(count.0.load(Ordering::Relaxed), 1);
+}
+
+#[actix_web::test]
+async fn responds_with_the_headers_of_the_error() {
+    let app = test::init_service(App::new().route("/users", web::get().to(list_users))).await;
+    let request = test::TestRequest::get().uri("/users").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers().get("retry-after").unwrap(), "5");
+}
@@ -0,0 +1,47 @@
+//! Only built with neither `anyhow` nor `eyre`, eg.
+//! `cargo test --no-default-features --features std,tracing --test boxed`
+#![cfg(all(feature = "std", not(feature = "anyhow"), not(feature = "eyre")))]
+
+use api_error::{e, DetailedError, ToResponse};
+use thiserror::Error as ThisError;
+
+use std::io;
+
+#[derive(Debug, ThisError)]
+#[error("the config couldn't be loaded")]
+struct ConfigError(#[source] io::Error);
+
+#[derive(Debug)]
+struct PublicError;
+
+impl ToResponse for PublicError {
+    type Response = &'static str;
+
+    fn to_response(&self) -> Self::Response {
+        "unavailable"
+    }
+}
+
+#[test]
+fn builds_with_the_boxed_backend() {
+    let private = ConfigError(io::Error::new(io::ErrorKind::NotFound, "config.toml"));
+    let err: DetailedError<PublicError, &str> =
+        e!(private, PublicError, "Config", "starting the server");
+
+    let chain: Vec<String> = err.chain().map(|cause| cause.to_string()).collect();
+    assert_eq!(
+        chain,
+        [
+            "starting the server",
+            "the config couldn't be loaded",
+            "config.toml"
+        ]
+    );
+    assert_eq!(err.root_cause().to_string(), "config.toml");
+    assert_eq!(
+        err.downcast_ref::<ConfigError>()
+            .map(|config| config.0.kind()),
+        Some(io::ErrorKind::NotFound)
+    );
+    assert!(err.downcast::<ConfigError>().is_ok());
+}
@@ -0,0 +1,49 @@
+//! Only built with the `derive` feature, eg. `cargo test --features derive --test derive`
+#![cfg(feature = "derive")]
+
+use api_error::Category;
+
+use std::fmt;
+
+#[derive(Debug, Category)]
+enum ApiCategory {
+    #[category(status = 404, code = "USER_NOT_FOUND")]
+    UserNotFound { id: u64 },
+    #[category(status = 503, code = "UPSTREAM_UNAVAILABLE")]
+    Upstream(&'static str),
+    #[category(status = 500, code = "INTERNAL")]
+    Internal,
+}
+
+impl fmt::Display for ApiCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiCategory::UserNotFound { id } => write!(f, "UserNotFound({})", id),
+            ApiCategory::Upstream(service) => write!(f, "Upstream({})", service),
+            ApiCategory::Internal => write!(f, "Internal"),
+        }
+    }
+}
+
+#[test]
+fn every_variant_has_a_status_and_code() {
+    let cases = [
+        (ApiCategory::UserNotFound { id: 42 }, 404, "USER_NOT_FOUND"),
+        (
+            ApiCategory::Upstream("billing"),
+            503,
+            "UPSTREAM_UNAVAILABLE",
+        ),
+        (ApiCategory::Internal, 500, "INTERNAL"),
+    ];
+    for (category, status, code) in cases {
+        assert_eq!(category.status_code(), status);
+        assert_eq!(category.code().as_str(), code);
+    }
+}
+
+#[test]
+fn missing_attributes_fail_to_compile() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,100 @@
+//! Only built without the `std` feature, eg.
+//! `cargo test --no-default-features --features anyhow,tracing --test no_std`
+#![cfg(not(feature = "std"))]
+
+use api_error::{DetailedError, FieldValue, Level, ToResponse};
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+#[error("the sensor didn't respond")]
+struct SensorError;
+
+#[derive(Debug)]
+struct PublicError;
+
+impl ToResponse for PublicError {
+    type Response = &'static str;
+
+    fn to_response(&self) -> Self::Response {
+        "unavailable"
+    }
+}
+
+#[test]
+fn builds_and_logs_without_std() {
+    let mut err = DetailedError::new(SensorError, PublicError, "Hardware", Level::WARN)
+        .context("reading the temperature")
+        .with_field("sensor", 3u8);
+    err.log();
+
+    assert_eq!(err.to_response(), "unavailable");
+    assert_eq!(err.to_string(), "reading the temperature");
+    assert_eq!(
+        err.chain().nth(1).unwrap().to_string(),
+        "the sensor didn't respond"
+    );
+    assert_eq!(FieldValue::from(3u8), FieldValue::U64(3));
+}
+
+#[cfg(feature = "tracing")]
+mod events {
+    use super::*;
+
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+    use tracing_subscriber::Registry;
+
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Fields(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl<S: Subscriber> Layer<S> for Fields {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            event.record(&mut Record(&mut self.0.lock().unwrap()));
+        }
+    }
+
+    struct Record<'a>(&'a mut Vec<(String, String)>);
+
+    impl Visit for Record<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    #[test]
+    fn emits_the_same_layout_as_with_std() {
+        let fields = Fields::default();
+        let subscriber = Registry::default().with(fields.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            DetailedError::new(SensorError, PublicError, "Hardware", Level::WARN)
+                .context("reading the temperature")
+                .with_field("sensor", 3u8)
+                .with_field("line", "a clash")
+                .log();
+        });
+
+        let fields = fields.0.lock().unwrap();
+        let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "errors",
+                "public_error",
+                "category",
+                "additional_context.line",
+                "sensor",
+                "file",
+                "line",
+                "message"
+            ]
+        );
+        assert_eq!(fields[0].1, r#"["the sensor didn't respond"]"#);
+        assert_eq!(fields[4].1, "3");
+        assert_eq!(fields[7].1, "reading the temperature");
+    }
+}
@@ -0,0 +1,10 @@
+use api_error::Category;
+
+#[derive(Debug, Category)]
+enum ApiCategory {
+    #[category(status = 404, code = "NOT_FOUND")]
+    NotFound,
+    Internal,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use api_error::Category;
+
+#[derive(Debug, Category)]
+enum ApiCategory {
+    #[category(status = 404)]
+    NotFound,
+}
+
+fn main() {}